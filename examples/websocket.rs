@@ -4,8 +4,8 @@ use backpacktf_api::websocket::{Error, connect};
 async fn main() {
     match connect().await {
         Ok(mut websocket) => {
-             while let Some((_, message)) = websocket.recv().await {
-                println!("{message}");
+             while let Some(event) = websocket.recv().await {
+                println!("{}", event.message);
             }
         },
         // Server responded with an HTTP error
@@ -2,7 +2,8 @@
 //! API to deserialize responses.
 
 use crate::time::ServerTime;
-use crate::response;
+use crate::error::Error;
+use crate::{request, response};
 use crate::response::deserializers;
 use serde::{Serialize, Deserialize};
 use chrono::serde::ts_seconds_option;
@@ -65,10 +66,69 @@ pub struct DeleteListingsResult {
     pub deleted: u32,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct UpdateListingsError {
+    pub index: usize,
+    pub message: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdateListingsResponse {
+    pub updated: Vec<response::listing::update_listing::SuccessListing>,
+    pub errors: Vec<UpdateListingsError>,
+}
+
+impl UpdateListingsResponse {
+    /// Converts the response into results in the same order as the queries. Errors are placed 
+    /// using their index and updated listings are matched to the remaining queries by ID. An 
+    /// updated listing which doesn't match any remaining query is an error.
+    #[allow(clippy::result_large_err)]
+    pub fn into_results<'a, T>(
+        self,
+        listings: &[&'a request::UpdateListing<T>],
+    ) -> Result<Vec<response::listing::update_listing::Result<'a, T>>, Error> {
+        if self.updated.len() + self.errors.len() != listings.len() {
+            return Err(Error::Response("Results and query have different number of listings".into()));
+        }
+        
+        let mut results = std::iter::repeat_with(|| None)
+            .take(listings.len())
+            .collect::<Vec<_>>();
+        
+        for error in self.errors {
+            if let Some(query) = listings.get(error.index).copied() {
+                results[error.index] = Some(Err(response::listing::update_listing::ErrorListing {
+                    message: error.message,
+                    query,
+                }));
+            } else {
+                // probably shouldn't ever happen but who knows
+                return Err(Error::Response(format!("Missing index `{}`: {}", error.index, error.message)));
+            }
+        }
+        
+        for listing in self.updated {
+            let position = listings
+                .iter()
+                .enumerate()
+                .position(|(index, query)| results[index].is_none() && query.id == listing.id);
+            
+            if let Some(position) = position {
+                results[position] = Some(Ok(listing));
+            } else {
+                return Err(Error::Response(format!("No query for updated listing `{}`", listing.id)));
+            }
+        }
+        
+        Ok(results.into_iter().flatten().collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::SteamID;
+    use crate::response::listing::update_listing::FindByListingId;
     use tf2_enum::Paint;
 
     #[test]
@@ -79,6 +139,49 @@ mod tests {
         assert_eq!(deleted, 5);
     }
     
+    #[test]
+    fn update_listings_results_are_in_query_order() {
+        let response: UpdateListingsResponse = serde_json::from_str(include_str!("fixtures/update_listings.json")).unwrap();
+        let listings = [
+            request::UpdateListing {
+                id: "440_7764221391".to_string(),
+                currencies: (),
+                details: None,
+            },
+            request::UpdateListing {
+                id: "440_1234".to_string(),
+                currencies: (),
+                details: None,
+            },
+            request::UpdateListing {
+                id: "440_76561198080179568_76c096345919b66f01980381017e31e8".to_string(),
+                currencies: (),
+                details: None,
+            },
+        ];
+        let results = response.into_results(&listings.iter().collect::<Vec<_>>()).unwrap();
+        
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().id, listings[0].id);
+        assert_eq!(results[1].as_ref().unwrap_err().query.id, listings[1].id);
+        assert_eq!(results[2].as_ref().unwrap().id, listings[2].id);
+        assert!(results.find_by_listing_id("440_1234").unwrap().is_err());
+        assert!(results.find_by_listing_id("440_5678").is_none());
+    }
+    
+    #[test]
+    fn update_listings_results_must_match_queries() {
+        let response: UpdateListingsResponse = serde_json::from_str(include_str!("fixtures/update_listings.json")).unwrap();
+        let listings = ["440_7764221391", "440_1234", "440_5678"].map(|id| request::UpdateListing {
+            id: id.to_string(),
+            currencies: (),
+            details: None,
+        });
+        let error = response.into_results(&listings.iter().collect::<Vec<_>>()).unwrap_err();
+        
+        assert!(matches!(error, Error::Response(message) if message.contains("440_76561198080179568_76c096345919b66f01980381017e31e8")));
+    }
+    
     #[test]
     fn parses_get_users() {
         let response: GetUsersResponseWrapper = serde_json::from_str(include_str!("fixtures/get_users.json")).unwrap();
@@ -1,13 +1,15 @@
 use super::{api_response, helpers};
-use crate::{SteamID, BackpackAPIBuilder, ListingIntent};
-use crate::error::{Error, ParameterError};
+use crate::{SteamID, BackpackAPIBuilder, BatchRetryPolicy, CancellationToken, ListingIntent};
+use crate::error::{Error, ParameterError, DRY_RUN_MESSAGE};
 use crate::currency_type::CurrencyType;
 use crate::response;
 use crate::request::{self, listing_serializers::option_buy_listing_item_into_params, serializers};
 use std::borrow::Borrow;
+use std::fmt;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use async_std::task::sleep;
 use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use reqwest::Method;
 use reqwest_middleware::ClientWithMiddleware;
 
 const RESPONSE_UNSUCCESSFUL_MESSAGE: &str = "Empty response";
@@ -21,6 +23,10 @@ pub struct BackpackAPI {
     key: Option<String>,
     token: Option<String>,
     client: ClientWithMiddleware,
+    batch_retry_policy: Option<BatchRetryPolicy>,
+    dry_run: bool,
+    dry_run_requests: Arc<Mutex<Vec<request::DryRunRequest>>>,
+    validate_listings: bool,
 }
 
 impl Default for BackpackAPI {
@@ -42,11 +48,18 @@ impl BackpackAPI {
         key: Option<String>,
         token: Option<String>,
         client: ClientWithMiddleware,
+        batch_retry_policy: Option<BatchRetryPolicy>,
+        dry_run: bool,
+        validate_listings: bool,
     ) -> Self {
         Self {
             key,
             token,
             client,
+            batch_retry_policy,
+            dry_run,
+            dry_run_requests: Arc::new(Mutex::new(Vec::new())),
+            validate_listings,
         }
     }
     
@@ -76,6 +89,120 @@ impl BackpackAPI {
         }
     }
     
+    /// In dry-run mode, records the request which would have been sent and returns it. Otherwise 
+    /// returns `None`.
+    #[allow(clippy::result_large_err)]
+    fn record_dry_run<Q, B>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        query: &Q,
+        body: &B,
+    ) -> Result<Option<request::DryRunRequest>, Error>
+    where
+        Q: Serialize,
+        B: Serialize,
+    {
+        if !self.dry_run {
+            return Ok(None);
+        }
+        
+        let request = request::DryRunRequest::new(
+            method,
+            self.get_api_uri(endpoint),
+            query,
+            body,
+        )?;
+        
+        self.dry_run_requests.lock().unwrap().push(request.clone());
+        Ok(Some(request))
+    }
+    
+    /// In dry-run mode, records the request which would have been sent and returns it as 
+    /// [`Error::DryRun`]. Otherwise does nothing.
+    #[allow(clippy::result_large_err)]
+    fn check_dry_run<Q, B>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        query: &Q,
+        body: &B,
+    ) -> Result<(), Error>
+    where
+        Q: Serialize,
+        B: Serialize,
+    {
+        match self.record_dry_run(method, endpoint, query, body)? {
+            Some(request) => Err(Error::DryRun(Box::new(request))),
+            None => Ok(()),
+        }
+    }
+    
+    /// Gets the requests recorded in dry-run mode, in the order they would have been sent. 
+    /// Clones of the client share the same record.
+    pub fn dry_run_requests(&self) -> Vec<request::DryRunRequest> {
+        self.dry_run_requests.lock().unwrap().clone()
+    }
+    
+    /// Takes the requests recorded in dry-run mode, clearing the record.
+    pub fn take_dry_run_requests(&self) -> Vec<request::DryRunRequest> {
+        std::mem::take(&mut *self.dry_run_requests.lock().unwrap())
+    }
+    
+    /// Whether requests are recorded instead of sent. Requests which are not sent don't need to 
+    /// be rate limited.
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+    
+    /// When listing validation is enabled, returns [`ParameterError::InvalidListing`] for the 
+    /// first listing with issues. The issues are only evaluated when validation is enabled.
+    #[allow(clippy::result_large_err)]
+    fn check_valid<I>(
+        &self,
+        issues: I,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Vec<request::ValidationIssue>>,
+    {
+        if !self.validate_listings {
+            return Ok(());
+        }
+        
+        for (index, issues) in issues.into_iter().enumerate() {
+            if !issues.is_empty() {
+                return Err(ParameterError::InvalidListing {
+                    index,
+                    issues,
+                }.into());
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Checks that a batch of listings is not empty and does not exceed the request limit.
+    #[allow(clippy::result_large_err)]
+    fn check_listings_length(
+        len: usize,
+        name: &'static str,
+    ) -> Result<(), Error> {
+        if len == 0 {
+            return Err(ParameterError::Empty {
+                name,
+            }.into());
+        }
+        
+        if len > MAX_LISTINGS_REQUEST_LIMIT {
+            return Err(ParameterError::MaximumLengthExceeded {
+                name,
+                max: MAX_LISTINGS_REQUEST_LIMIT,
+            }.into());
+        }
+        
+        Ok(())
+    }
+    
     /// Sends a GET request.
     async fn get<T, D>(
         &self,
@@ -314,17 +441,21 @@ impl BackpackAPI {
             blanket = None;
         }
         
+        let params = Params {
+            token,
+            item_name,
+            intent,
+            currency,
+            min,
+            max,
+            blanket,
+        };
+        
+        self.check_dry_run(Method::POST, "/classifieds/alerts", &(), &params)?;
+        
         let alert: response::alert::Alert = self.post_json(
             "/classifieds/alerts",
-            &Params {
-                token,
-                item_name,
-                intent,
-                currency,
-                min,
-                max,
-                blanket,
-            },
+            &params,
         ).await?;
         
         Ok(alert)
@@ -345,14 +476,17 @@ impl BackpackAPI {
         }
         
         let token = self.get_token()?;
+        let params = Params {
+            token,
+            item_name,
+            intent,
+        };
+        
+        self.check_dry_run(Method::DELETE, "/classifieds/alerts", &params, &())?;
         
         self.delete(
             "/classifieds/alerts",
-            &Params {
-                token,
-                item_name,
-                intent,
-            },
+            &params,
         ).await
     }
 
@@ -362,9 +496,12 @@ impl BackpackAPI {
         id: &str,
     ) -> Result<(), Error> {
         let token = self.get_token()?;
+        let endpoint = format!("/classifieds/alerts/{id}");
+        
+        self.check_dry_run(Method::DELETE, &endpoint, &Token { token }, &())?;
         
         self.delete(
-            &format!("/classifieds/alerts/{id}"),
+            &endpoint,
             &Token {
                 token,
             },
@@ -581,13 +718,19 @@ impl BackpackAPI {
         id: &str,
     ) -> Result<(), Error> {
         let token = self.get_token()?;
+        let endpoint = format!("/v2/classifieds/archive/{id}");
         
-        self.delete(
-            &format!("/v2/classifieds/archive/{id}"),
-            &Token {
+        self.check_dry_run(Method::DELETE, &endpoint, &Token { token }, &())?;
+        
+        let uri = self.get_api_uri(&endpoint);
+        let response = self.client.delete(uri)
+            .query(&Token {
                 token,
-            },
-        ).await
+            })
+            .send()
+            .await?;
+        
+        helpers::check_response(response)
     }
     
     /// Deletes all listings from the archive.
@@ -595,6 +738,9 @@ impl BackpackAPI {
         &self,
     ) -> Result<(), Error> {
         let token = self.get_token()?;
+        
+        self.check_dry_run(Method::DELETE, "/v2/classifieds/archive", &(), &Token { token })?;
+        
         let uri = self.get_api_uri("/v2/classifieds/archive");
         let _response = self.client.delete(uri)
             .json(&Token {
@@ -608,8 +754,8 @@ impl BackpackAPI {
         Ok(())
     }
     
-    /// Deletes listings from the archive. A limit of 100 listings is imposed. Currently does not 
-    /// work.
+    /// Deletes listings from the archive with a single batch request. A limit of 100 listings is 
+    /// imposed. Returns the number of listings deleted.
     pub async fn delete_archived_listings<T>(
         &self,
         listing_ids: &[T],
@@ -622,25 +768,20 @@ impl BackpackAPI {
             listing_ids: &'a [T],
         }
         
-        if listing_ids.is_empty() {
-            return Err(ParameterError::Empty {
-                name: "listing_ids",
-            }.into());
-        }
+        Self::check_listings_length(listing_ids.len(), "listing_ids")?;
         
-        if listing_ids.len() > MAX_LISTINGS_REQUEST_LIMIT {
-            return Err(ParameterError::MaximumLengthExceeded {
-                name: "listing_ids",
-                max: MAX_LISTINGS_REQUEST_LIMIT,
-            }.into());
+        let token = self.get_token()?;
+        let params = Params {
+            listing_ids,
+        };
+        
+        if self.record_dry_run(Method::DELETE, "/v2/classifieds/archive/batch", &Token { token }, &params)?.is_some() {
+            return Ok(0);
         }
         
-        let token = self.get_token()?;
         let uri = self.get_api_uri("/v2/classifieds/archive/batch");
         let response = self.client.delete(uri)
-            .json(&Params {
-                listing_ids,
-            })
+            .json(&params)
             .query(&Token {
                 token,
             })
@@ -659,6 +800,22 @@ impl BackpackAPI {
         details: Option<String>,
         currencies: &T,
     ) -> Result<response::listing::update_listing::SuccessListing, Error>
+    where
+        T: Serialize
+    {
+        self.check_valid(std::iter::once_with(|| {
+            request::validation::validate_update(id, currencies, &details)
+        }))?;
+        self.update_archived_listing_unchecked(id, details, currencies).await
+    }
+    
+    /// Updates a listing from the archive without validating it.
+    async fn update_archived_listing_unchecked<T>(
+        &self,
+        id: &str,
+        details: Option<String>,
+        currencies: &T,
+    ) -> Result<response::listing::update_listing::SuccessListing, Error>
     where
         T: Serialize
     {
@@ -669,12 +826,17 @@ impl BackpackAPI {
         }
         
         let token = self.get_token()?;
-        let uri = self.get_api_uri(&format!("/v2/classifieds/archive/{id}"));
+        let endpoint = format!("/v2/classifieds/archive/{id}");
+        let params = JSONParams {
+            currencies,
+            details,
+        };
+        
+        self.check_dry_run(Method::PATCH, &endpoint, &Token { token }, &params)?;
+        
+        let uri = self.get_api_uri(&endpoint);
         let response = self.client.patch(uri)
-            .json(&JSONParams {
-                currencies,
-                details,
-            })
+            .json(&params)
             .query(&Token {
                 token,
             })
@@ -691,17 +853,106 @@ impl BackpackAPI {
         id: &str,
     ) -> Result<(), Error> {
         let token = self.get_token()?;
-        let uri = self.get_api_uri(&format!("/v2/classifieds/archive/{id}/publish"));
-        let _response = self.client.post(uri)
+        let endpoint = format!("/v2/classifieds/archive/{id}/publish");
+        
+        self.check_dry_run(Method::POST, &endpoint, &Token { token }, &())?;
+        
+        let uri = self.get_api_uri(&endpoint);
+        let response = self.client.post(uri)
             .query(&Token {
                 token,
             })
             .send()
             .await?;
         
-        // todo check the response
+        helpers::check_response(response)
+    }
             
-        Ok(())
+    /// Publishes listings from the archive to the active pool. A limit of 100 listings is 
+    /// imposed. Each listing is published with its own rate-limited request and results are in 
+    /// the same order as the input listing IDs.
+    pub async fn publish_archived_listings<'a, T>(
+        &self,
+        listing_ids: &'a [T],
+    ) -> Result<Vec<response::listing::archived_listing::Result<'a>>, Error>
+    where
+        T: Borrow<String>,
+    {
+        self.publish_archived_listings_refs(listing_ids.iter().collect()).await
+    }
+    
+    /// Publishes listings from the archive from references to the IDs. A limit of 100 listings 
+    /// is imposed.
+    async fn publish_archived_listings_refs<'a, T>(
+        &self,
+        listing_ids: Vec<&'a T>,
+    ) -> Result<Vec<response::listing::archived_listing::Result<'a>>, Error>
+    where
+        T: Borrow<String>,
+    {
+        Self::check_listings_length(listing_ids.len(), "listing_ids")?;
+        self.get_token()?;
+        
+        let results = helpers::submit_each(
+            listing_ids,
+            !self.dry_run,
+            &CancellationToken::new(),
+            |id| self.publish_archived_listing(id.borrow()),
+        ).await;
+        
+        Ok(into_archived_listing_results(results).await)
+    }
+    
+    /// Updates listings in the archive. A limit of 100 listings is imposed. Each listing is 
+    /// updated with its own rate-limited request and results are in the same order as the input 
+    /// listings. Note that any type can be used for the currencies parameter as long as it 
+    /// implements [`Serialize`].
+    pub async fn update_archived_listings<'a, T>(
+        &self,
+        listings: &'a [request::UpdateListing<T>],
+    ) -> Result<Vec<response::listing::update_listing::Result<'a, T>>, Error>
+    where
+        T: Serialize,
+    {
+        self.check_valid(listings.iter().map(|listing| listing.validate()))?;
+        self.update_archived_listings_refs(listings.iter().collect()).await
+    }
+    
+    /// Updates listings in the archive from references to the queries. A limit of 100 listings 
+    /// is imposed. The listings are not validated.
+    async fn update_archived_listings_refs<'a, T>(
+        &self,
+        listings: Vec<&'a request::UpdateListing<T>>,
+    ) -> Result<Vec<response::listing::update_listing::Result<'a, T>>, Error>
+    where
+        T: Serialize,
+    {
+        Self::check_listings_length(listings.len(), "listings")?;
+        self.get_token()?;
+        
+        let results = helpers::submit_each(
+            listings,
+            !self.dry_run,
+            &CancellationToken::new(),
+            |listing| self.update_archived_listing_unchecked(
+                &listing.id,
+                listing.details.clone(),
+                &listing.currencies,
+            ),
+        ).await;
+        let mut mapped = Vec::with_capacity(results.len());
+        
+        for (query, result) in results {
+            mapped.push(match result {
+                Ok(listing) => Ok(listing),
+                Err(error) => Err(response::listing::update_listing::ErrorListing {
+                    message: helpers::error_message(error).await,
+                    query,
+                }),
+            });
+        }
+        
+        Ok(mapped)
     }
 
     /// Gets a listing.
@@ -775,6 +1026,8 @@ impl BackpackAPI {
             currencies: &'b T,
         }
         
+        self.check_valid(std::iter::once_with(|| listing.validate()))?;
+        
         let token = self.get_token()?;
         let params: Params<T> = match listing {
             request::CreateListing::Buy {
@@ -810,6 +1063,9 @@ impl BackpackAPI {
                 currencies,
             },
         };
+        
+        self.check_dry_run(Method::POST, "/v2/classifieds/listings", &(), &params)?;
+        
         let listing: response::listing::Listing = self.post_json(
             "/v2/classifieds/listings",
             &params,
@@ -819,11 +1075,25 @@ impl BackpackAPI {
     }
     
     /// Creates listings. A limit of 100 listings is imposed. Note that any type can be used for 
-    /// the currencies parameter as long as it implements [`Serialize`].
+    /// the currencies parameter as long as it implements [`Serialize`]. Results are in the same 
+    /// order as the input listings.
     pub async fn create_listings<'a, T>(
         &self,
         listings: &'a [request::CreateListing<T>],
     ) -> Result<Vec<response::listing::create_listing::Result<'a, T>>, Error>
+    where
+        T: Serialize
+    {
+        self.check_valid(listings.iter().map(|listing| listing.validate()))?;
+        self.create_listings_refs(listings.iter().collect()).await
+    }
+    
+    /// Creates listings from references to the queries. A limit of 100 listings is imposed. The 
+    /// listings are not validated.
+    async fn create_listings_refs<'a, T>(
+        &self,
+        listings: Vec<&'a request::CreateListing<T>>,
+    ) -> Result<Vec<response::listing::create_listing::Result<'a, T>>, Error>
     where
         T: Serialize
     {
@@ -852,6 +1122,17 @@ impl BackpackAPI {
         }
         
         let token = self.get_token()?;
+        
+        if self.record_dry_run(Method::POST, "/v2/classifieds/listings/batch", &Token { token }, &listings)?.is_some() {
+            return Ok(listings
+                .into_iter()
+                .map(|query| Err(response::listing::create_listing::ErrorListing {
+                    message: DRY_RUN_MESSAGE.into(),
+                    query,
+                }))
+                .collect());
+        }
+        
         let uri = self.get_api_uri("/v2/classifieds/listings/batch");
         let response = self.client.post(uri)
             .query(&Token {
@@ -888,19 +1169,29 @@ impl BackpackAPI {
         Ok(results)
     }
 
-    /// Deletes a listing.
-    pub async fn delete_listing(
+    /// Deletes a listing. The ID can be a [`ListingId`](crate::ListingId) or anything else which 
+    /// formats as a listing ID, such as a `&str`.
+    pub async fn delete_listing<I>(
         &self,
-        id: &str,
-    ) -> Result<(), Error> {
+        id: I,
+    ) -> Result<(), Error>
+    where
+        I: fmt::Display,
+    {
         let token = self.get_token()?;
+        let endpoint = format!("/v2/classifieds/listings/{id}");
         
-        self.delete(
-            &format!("/v2/classifieds/listings/{id}"),
-            &Token {
+        self.check_dry_run(Method::DELETE, &endpoint, &Token { token }, &())?;
+        
+        let uri = self.get_api_uri(&endpoint);
+        let response = self.client.delete(uri)
+            .query(&Token {
                 token,
-            },
-        ).await
+            })
+            .send()
+            .await?;
+        
+        helpers::check_response(response)
     }
     
     /// Deletes listings. A limit of 100 listings is imposed.
@@ -931,12 +1222,18 @@ impl BackpackAPI {
         }
         
         let token = self.get_token()?;
+        let params = Params {
+            token,
+            listing_ids,
+        };
+        
+        if self.record_dry_run(Method::DELETE, "/classifieds/delete/v1", &(), &params)?.is_some() {
+            return Ok(0);
+        }
+        
         let uri = self.get_api_uri("/classifieds/delete/v1");
         let response = self.client.delete(uri)
-            .json(&Params {
-                token,
-                listing_ids,
-            })
+            .json(&params)
             .send()
             .await?;
         let response: api_response::DeleteListingsResult = helpers::parses_response(response).await?;
@@ -944,15 +1241,17 @@ impl BackpackAPI {
         Ok(response.deleted)
     }
     
-    /// Updates a listing. Note that any type can be used for the currencies parameter as long as 
-    /// it implements [`Serialize`].
-    pub async fn update_listing<T>(
+    /// Updates a listing. The ID can be a [`ListingId`](crate::ListingId) or anything else which 
+    /// formats as a listing ID, such as a `&str`. Note that any type can be used for the 
+    /// currencies parameter as long as it implements [`Serialize`].
+    pub async fn update_listing<I, T>(
         &self,
-        id: &str,
+        id: I,
         details: Option<String>,
         currencies: &T,
     ) -> Result<response::listing::update_listing::SuccessListing, Error>
     where
+        I: fmt::Display,
         T: Serialize
     {
         #[derive(Serialize, Debug)]
@@ -961,13 +1260,24 @@ impl BackpackAPI {
             details: Option<String>,
         }
         
+        let id = id.to_string();
+        
+        self.check_valid(std::iter::once_with(|| {
+            request::validation::validate_update(&id, currencies, &details)
+        }))?;
+        
         let token = self.get_token()?;
-        let uri = self.get_api_uri(&format!("/v2/classifieds/listings/{id}"));
+        let endpoint = format!("/v2/classifieds/listings/{id}");
+        let params = JSONParams {
+            currencies,
+            details,
+        };
+        
+        self.check_dry_run(Method::PATCH, &endpoint, &Token { token }, &params)?;
+        
+        let uri = self.get_api_uri(&endpoint);
         let response = self.client.patch(uri)
-            .json(&JSONParams {
-                currencies,
-                details,
-            })
+            .json(&params)
             .query(&Token {
                 token,
             })
@@ -979,7 +1289,8 @@ impl BackpackAPI {
     }
     
     /// Updates listings. A limit of 100 listings is imposed. Note that any type can be used for 
-    /// the currencies parameter as long as it implements [`Serialize`].
+    /// the currencies parameter as long as it implements [`Serialize`]. Results are in the same 
+    /// order as the input listings.
     pub async fn update_listings<'a, T>(
         &self,
         listings: &'a [request::UpdateListing<T>],
@@ -987,18 +1298,19 @@ impl BackpackAPI {
     where
         T: Serialize
     {
-        #[derive(Deserialize, Debug)]
-        struct ErrorResult {
-            index: usize,
-            message: String,
-        }
-        
-        #[derive(Deserialize, Debug)]
-        struct UpdateListingsResponse {
-            updated: Vec<response::listing::update_listing::SuccessListing>,
-            errors: Vec<ErrorResult>,
-        }
+        self.check_valid(listings.iter().map(|listing| listing.validate()))?;
+        self.update_listings_refs(listings.iter().collect()).await
+    }
         
+    /// Updates listings from references to the queries. A limit of 100 listings is imposed. The 
+    /// listings are not validated.
+    async fn update_listings_refs<'a, T>(
+        &self,
+        listings: Vec<&'a request::UpdateListing<T>>,
+    ) -> Result<Vec<response::listing::update_listing::Result<'a, T>>, Error>
+    where
+        T: Serialize
+    {
         #[derive(Serialize, Debug)]
         pub struct Body<'a, T> {
             currencies: &'a T,
@@ -1035,6 +1347,17 @@ impl BackpackAPI {
             })
             .collect::<Vec<_>>();
         let token = self.get_token()?;
+        
+        if self.record_dry_run(Method::PATCH, "/v2/classifieds/listings/batch", &Token { token }, &mapped)?.is_some() {
+            return Ok(listings
+                .into_iter()
+                .map(|query| Err(response::listing::update_listing::ErrorListing {
+                    message: DRY_RUN_MESSAGE.into(),
+                    query,
+                }))
+                .collect());
+        }
+        
         let uri = self.get_api_uri("/v2/classifieds/listings/batch");
         let response = self.client.patch(uri)
             .json(&mapped)
@@ -1043,30 +1366,9 @@ impl BackpackAPI {
             })
             .send()
             .await?;
-        let body: UpdateListingsResponse = helpers::parses_response(response).await?;
-        
-        if body.updated.len() + body.errors.len() != listings.len() {
-            return Err(Error::Response("Results and query have different number of listings".into()));
-        }
+        let body: api_response::UpdateListingsResponse = helpers::parses_response(response).await?;
         
-        let mut results = body.updated
-            .into_iter()
-            .map(Ok)
-            .collect::<Vec<_>>();
-        
-        for error in body.errors {
-            if let Some(query) = listings.get(error.index) {
-                results.push(Err(response::listing::update_listing::ErrorListing {
-                    message: error.message,
-                    query,
-                }))
-            } else {
-                // probably shouldn't ever happen but who knows
-                return Err(Error::Response(format!("Missing index `{}`: {}", error.index, error.message)));
-            }
-        }
-        
-        Ok(results)
+        body.into_results(&listings)
     }
     
     /// Sets a listing to promoted.
@@ -1075,8 +1377,12 @@ impl BackpackAPI {
         id: &str,
     ) -> Result<response::listing::Listing, Error> {
         let token = self.get_token()?;
+        let endpoint = format!("/v2/classifieds/listings/{id}/promote");
+        
+        self.check_dry_run(Method::POST, &endpoint, &(), &Token { token })?;
+        
         let listing: response::listing::Listing = self.post_json(
-            &format!("/v2/classifieds/listings/{id}/promote"),
+            &endpoint,
             &Token {
                 token,
             },
@@ -1091,15 +1397,39 @@ impl BackpackAPI {
         id: &str,
     ) -> Result<(), Error> {
         let token = self.get_token()?;
+        let endpoint = format!("/v2/classifieds/listings/{id}/demote");
+        
+        self.check_dry_run(Method::POST, &endpoint, &(), &Token { token })?;
         
         self.post_json(
-            &format!("/v2/classifieds/listings/{id}/demote"),
+            &endpoint,
             &Token {
                 token,
             },
         ).await
     }
     
+    /// Moves an active listing to the archive.
+    pub async fn archive_listing(
+        &self,
+        id: &str,
+    ) -> Result<(), Error> {
+        let token = self.get_token()?;
+        let endpoint = format!("/v2/classifieds/listings/{id}/archive");
+        
+        self.check_dry_run(Method::POST, &endpoint, &Token { token }, &())?;
+        
+        let uri = self.get_api_uri(&endpoint);
+        let response = self.client.post(uri)
+            .query(&Token {
+                token,
+            })
+            .send()
+            .await?;
+        
+        helpers::check_response(response)
+    }
+    
     /// Gets limits for batch requests.
     pub async fn get_listing_batch_limit(
         &self,
@@ -1179,12 +1509,26 @@ impl BackpackAPI {
     /// occurs, execution will cease and an error will be added to the return value.
     pub async fn get_all_alerts(
         &self,
+    ) -> (Vec<response::alert::Alert>, Option<Error>) {
+        self.get_all_alerts_cancellable(&CancellationToken::new()).await
+    }
+    
+    /// Gets all alerts. Same as [get_all_alerts](BackpackAPI::get_all_alerts) but stops 
+    /// after the page currently in flight when the token is cancelled, returning the alerts 
+    /// obtained so far along with [`Error::Cancelled`].
+    pub async fn get_all_alerts_cancellable(
+        &self,
+        cancel: &CancellationToken,
     ) -> (Vec<response::alert::Alert>, Option<Error>) {
         let mut all = Vec::new();
         let mut limit = MAX_ALERTS_REQUEST_LIMIT as u32;
         let mut skip = 0;
         
         loop {
+            if cancel.is_cancelled() {
+                return (all, Some(Error::Cancelled));
+            }
+            
             match self.get_alerts(skip, limit).await {
                 Ok((mut alerts, cursor)) => {
                     all.append(&mut alerts);
@@ -1196,12 +1540,12 @@ impl BackpackAPI {
                         break;
                     }
                     
-                    sleep(Duration::from_secs(4)).await;
+                    helpers::sleep_cancellable(Duration::from_secs(4), cancel).await;
                     continue;
                 },
                 Err(error) => {
                     if let Some(duration) = helpers::retryable_duration(&error) {
-                        sleep(duration).await;
+                        helpers::sleep_cancellable(duration, cancel).await;
                         continue;
                     }
                     
@@ -1219,12 +1563,27 @@ impl BackpackAPI {
     /// return value.
     pub async fn get_all_archived_listings(
         &self,
+    ) -> (Vec<response::listing::Listing>, Option<Error>) {
+        self.get_all_archived_listings_cancellable(&CancellationToken::new()).await
+    }
+    
+    /// Gets all archived listings. Same as 
+    /// [get_all_archived_listings](BackpackAPI::get_all_archived_listings) but stops after the 
+    /// page currently in flight when the token is cancelled, returning the listings obtained so 
+    /// far along with [`Error::Cancelled`].
+    pub async fn get_all_archived_listings_cancellable(
+        &self,
+        cancel: &CancellationToken,
     ) -> (Vec<response::listing::Listing>, Option<Error>) {
         let mut all = Vec::new();
         let mut limit = MAX_LISTINGS_REQUEST_LIMIT as u32;
         let mut skip = 0;
         
         loop {
+            if cancel.is_cancelled() {
+                return (all, Some(Error::Cancelled));
+            }
+            
             match self.get_archived_listings(skip, limit).await {
                 Ok((mut listings, cursor)) => {
                     all.append(&mut listings);
@@ -1236,12 +1595,12 @@ impl BackpackAPI {
                         break;
                     }
                     
-                    sleep(Duration::from_secs(4)).await;
+                    helpers::sleep_cancellable(Duration::from_secs(4), cancel).await;
                     continue;
                 },
                 Err(error) => {
                     if let Some(duration) = helpers::retryable_duration(&error) {
-                        sleep(duration).await;
+                        helpers::sleep_cancellable(duration, cancel).await;
                         continue;
                     }
                     
@@ -1258,12 +1617,26 @@ impl BackpackAPI {
     /// error occurs, execution will cease and an error will be added to the return value.
     pub async fn get_all_listings(
         &self,
+    ) -> (Vec<response::listing::Listing>, Option<Error>) {
+        self.get_all_listings_cancellable(&CancellationToken::new()).await
+    }
+    
+    /// Gets all listings. Same as [get_all_listings](BackpackAPI::get_all_listings) but stops 
+    /// after the page currently in flight when the token is cancelled, returning the listings 
+    /// obtained so far along with [`Error::Cancelled`].
+    pub async fn get_all_listings_cancellable(
+        &self,
+        cancel: &CancellationToken,
     ) -> (Vec<response::listing::Listing>, Option<Error>) {
         let mut all = Vec::new();
         let mut limit = MAX_LISTINGS_REQUEST_LIMIT as u32;
         let mut skip = 0;
         
         loop {
+            if cancel.is_cancelled() {
+                return (all, Some(Error::Cancelled));
+            }
+            
             match self.get_listings(skip, limit).await {
                 Ok((mut listings, cursor)) => {
                     all.append(&mut listings);
@@ -1275,12 +1648,12 @@ impl BackpackAPI {
                         break;
                     }
                     
-                    sleep(Duration::from_secs(4)).await;
+                    helpers::sleep_cancellable(Duration::from_secs(4), cancel).await;
                     continue;
                 },
                 Err(error) => {
                     if let Some(duration) = helpers::retryable_duration(&error) {
-                        sleep(duration).await;
+                        helpers::sleep_cancellable(duration, cancel).await;
                         continue;
                     }
                     
@@ -1297,11 +1670,22 @@ impl BackpackAPI {
     /// [get_all_archived_listings](BackpackAPI::get_all_archived_listings)
     pub async fn get_all_listings_and_archived(
         &self,
+    ) -> (Vec<response::listing::Listing>, Option<Error>) {
+        self.get_all_listings_and_archived_cancellable(&CancellationToken::new()).await
+    }
+    
+    /// Gets all listings and archived listings. Same as 
+    /// [get_all_listings_and_archived](BackpackAPI::get_all_listings_and_archived) but stops 
+    /// after the page currently in flight when the token is cancelled, returning the listings 
+    /// obtained so far along with [`Error::Cancelled`].
+    pub async fn get_all_listings_and_archived_cancellable(
+        &self,
+        cancel: &CancellationToken,
     ) -> (Vec<response::listing::Listing>, Option<Error>) {
         let (
             mut listings,
             listings_error,
-        ) = self.get_all_listings().await;
+        ) = self.get_all_listings_cancellable(cancel).await;
         
         if let Some(error) = listings_error {
             return (listings, Some(error));
@@ -1310,7 +1694,7 @@ impl BackpackAPI {
         let (
             mut archived_listings,
             archived_listings_error,
-        ) = self.get_all_archived_listings().await;
+        ) = self.get_all_archived_listings_cancellable(cancel).await;
         
         listings.append(&mut archived_listings);
         (listings, archived_listings_error)
@@ -1321,6 +1705,9 @@ impl BackpackAPI {
     /// number of requests per minute. If an error occurs, execution will cease and an error will 
     /// be added to the return value. Note that any type can be used for the currencies parameter 
     /// as long as it implements [`Serialize`].
+    /// 
+    /// If a [`BatchRetryPolicy`] is set, listings which failed with a retryable error are 
+    /// resubmitted after the other chunks. Results are in the same order as the input listings.
     pub async fn create_listings_chunked<'a, T>(
         &self,
         listings: &'a [request::CreateListing<T>],
@@ -1328,31 +1715,35 @@ impl BackpackAPI {
     where
         T: Serialize
     {
-        let mut chunked = helpers::Cooldown::new(listings);
-        let mut all = Vec::new();
+        self.create_listings_chunked_cancellable(listings, &CancellationToken::new()).await
+    }
         
-        while let Some((listings, duration)) = chunked.next() {
-            match self.create_listings(listings).await {
-                Ok(mut more_created) => {
-                    all.append(&mut more_created);
-                    
-                    if let Some(duration) = duration {
-                        sleep(duration).await;
-                    }
-                },
-                Err(error) => {
-                    if let Some(duration) = helpers::retryable_duration(&error) {
-                        sleep(duration).await;
-                        chunked.go_back();
-                        continue;
-                    }
-                    
-                    return (all, Some(error))
-                },
-            }
+    /// Bulk creates any number of listings. Same as 
+    /// [create_listings_chunked](BackpackAPI::create_listings_chunked) but stops after the chunk 
+    /// currently in flight when the token is cancelled, returning the results of the chunks 
+    /// completed so far along with [`Error::Cancelled`].
+    pub async fn create_listings_chunked_cancellable<'a, T>(
+        &self,
+        listings: &'a [request::CreateListing<T>],
+        cancel: &CancellationToken,
+    ) -> (Vec<response::listing::create_listing::Result<'a, T>>, Option<Error>)
+    where
+        T: Serialize
+    {
+        // Validate every listing up front so that no chunk is sent if any listing is invalid
+        if let Err(error) = self.check_valid(listings.iter().map(|listing| listing.validate())) {
+            return (Vec::new(), Some(error));
         }
         
-        (all, None)
+        helpers::submit_chunked(
+            listings,
+            MAX_LISTINGS_REQUEST_LIMIT,
+            !self.dry_run,
+            self.batch_retry_policy.as_ref(),
+            cancel,
+            |chunk| self.create_listings_refs(chunk),
+            |result| matches!(result, Err(error) if error.kind().is_retryable()),
+        ).await
     }
     
     /// Bulk updates any number of listings. This is a convenience method which handles mass 
@@ -1360,6 +1751,9 @@ impl BackpackAPI {
     /// number of requests per minute. If an error occurs, execution will cease and an error will 
     /// be added to the return value. Note that any type can be used for the currencies parameter 
     /// as long as it implements [`Serialize`].
+    /// 
+    /// If a [`BatchRetryPolicy`] is set, listings which failed with a retryable error are 
+    /// resubmitted after the other chunks. Results are in the same order as the input listings.
     pub async fn update_listings_chunked<'a, T>(
         &self,
         listings: &'a [request::UpdateListing<T>],
@@ -1367,31 +1761,35 @@ impl BackpackAPI {
     where
         T: Serialize
     {
-        let mut chunked = helpers::Cooldown::new(listings);
-        let mut all = Vec::new();
+        self.update_listings_chunked_cancellable(listings, &CancellationToken::new()).await
+    }
         
-        while let Some((listings, duration)) = chunked.next() {
-            match self.update_listings(listings).await {
-                Ok(mut more_updated) => {
-                    all.append(&mut more_updated);
-                    
-                    if let Some(duration) = duration {
-                        sleep(duration).await;
-                    }
-                },
-                Err(error) => {
-                    if let Some(duration) = helpers::retryable_duration(&error) {
-                        sleep(duration).await;
-                        chunked.go_back();
-                        continue;
-                    }
-                    
-                    return (all, Some(error))
-                },
-            }
+    /// Bulk updates any number of listings. Same as 
+    /// [update_listings_chunked](BackpackAPI::update_listings_chunked) but stops after the chunk 
+    /// currently in flight when the token is cancelled, returning the results of the chunks 
+    /// completed so far along with [`Error::Cancelled`].
+    pub async fn update_listings_chunked_cancellable<'a, T>(
+        &self,
+        listings: &'a [request::UpdateListing<T>],
+        cancel: &CancellationToken,
+    ) -> (Vec<response::listing::update_listing::Result<'a, T>>, Option<Error>)
+    where
+        T: Serialize
+    {
+        // Validate every listing up front so that no chunk is sent if any listing is invalid
+        if let Err(error) = self.check_valid(listings.iter().map(|listing| listing.validate())) {
+            return (Vec::new(), Some(error));
         }
         
-        (all, None)
+        helpers::submit_chunked(
+            listings,
+            MAX_LISTINGS_REQUEST_LIMIT,
+            !self.dry_run,
+            self.batch_retry_policy.as_ref(),
+            cancel,
+            |chunk| self.update_listings_refs(chunk),
+            |result| matches!(result, Err(error) if error.kind().is_retryable()),
+        ).await
     }
     
     /// Bulk deletes any number of listings. This is a convenience method which handles mass 
@@ -1405,21 +1803,41 @@ impl BackpackAPI {
     where
         T: Borrow<String> + Serialize,
     {
-        let mut chunked = helpers::Cooldown::new(listing_ids);
+        self.delete_listings_chunked_cancellable(listing_ids, &CancellationToken::new()).await
+    }
+    
+    /// Bulk deletes any number of listings. Same as 
+    /// [delete_listings_chunked](BackpackAPI::delete_listings_chunked) but stops after the chunk 
+    /// currently in flight when the token is cancelled, returning the number of listings deleted 
+    /// so far along with [`Error::Cancelled`].
+    pub async fn delete_listings_chunked_cancellable<T>(
+        &self,
+        listing_ids: &[T],
+        cancel: &CancellationToken,
+    ) -> (u32, Option<Error>) 
+    where
+        T: Borrow<String> + Serialize,
+    {
+        let mut chunked = helpers::Cooldown::new(listing_ids)
+            .rate_limited(!self.dry_run);
         let mut all = 0;
         
         while let Some((listing_ids, duration)) = chunked.next() {
+            if cancel.is_cancelled() {
+                return (all, Some(Error::Cancelled));
+            }
+            
             match self.delete_listings(listing_ids).await {
                 Ok(more_deleted) => {
                     all += more_deleted;
                     
                     if let Some(duration) = duration {
-                        sleep(duration).await;
+                        helpers::sleep_cancellable(duration, cancel).await;
                     }
                 },
                 Err(error) => {
                     if let Some(duration) = helpers::retryable_duration(&error) {
-                        sleep(duration).await;
+                        helpers::sleep_cancellable(duration, cancel).await;
                         chunked.go_back();
                         continue;
                     }
@@ -1443,21 +1861,41 @@ impl BackpackAPI {
     where
         T: Borrow<String> + Serialize,
     {
-        let mut chunked = helpers::Cooldown::new(listing_ids);
-        let mut all = 0;
+        self.delete_archived_listings_chunked_cancellable(listing_ids, &CancellationToken::new()).await
+    }
         
+    /// Bulk deletes any number of archived listings. Same as 
+    /// [delete_archived_listings_chunked](BackpackAPI::delete_archived_listings_chunked) but 
+    /// stops after the chunk currently in flight when the token is cancelled, returning the 
+    /// number of listings deleted so far along with [`Error::Cancelled`].
+    pub async fn delete_archived_listings_chunked_cancellable<T>(
+        &self,
+        listing_ids: &[T],
+        cancel: &CancellationToken,
+    ) -> (u32, Option<Error>)
+    where
+        T: Borrow<String> + Serialize,
+    {
+        let mut chunked = helpers::Cooldown::new(listing_ids)
+            .rate_limited(!self.dry_run);
+        let mut all = 0;
+                    
         while let Some((listing_ids, duration)) = chunked.next() {
+            if cancel.is_cancelled() {
+                return (all, Some(Error::Cancelled));
+            }
+            
             match self.delete_archived_listings(listing_ids).await {
                 Ok(more_deleted) => {
                     all += more_deleted;
                     
                     if let Some(duration) = duration {
-                        sleep(duration).await;
+                        helpers::sleep_cancellable(duration, cancel).await;
                     }
                 },
                 Err(error) => {
                     if let Some(duration) = helpers::retryable_duration(&error) {
-                        sleep(duration).await;
+                        helpers::sleep_cancellable(duration, cancel).await;
                         chunked.go_back();
                         continue;
                     }
@@ -1469,9 +1907,375 @@ impl BackpackAPI {
         
         (all, None)
     }
+                    
+    /// Bulk publishes any number of listings from the archive. This is a convenience method 
+    /// which handles mass publishing of archived listings, where each listing is published with 
+    /// its own request and requests are rate limited to a certain number per minute. If an error 
+    /// occurs, execution will cease and an error will be added to the return value.
+    /// 
+    /// If a [`BatchRetryPolicy`] is set, listings which failed with a retryable error are 
+    /// resubmitted after the other listings. Results are in the same order as the input listing IDs.
+    pub async fn publish_archived_listings_chunked<'a, T>(
+        &self,
+        listing_ids: &'a [T],
+    ) -> (Vec<response::listing::archived_listing::Result<'a>>, Option<Error>)
+    where
+        T: Borrow<String>,
+    {
+        self.publish_archived_listings_chunked_cancellable(listing_ids, &CancellationToken::new()).await
+    }
+    
+    /// Bulk publishes any number of listings from the archive. Same as 
+    /// [publish_archived_listings_chunked](BackpackAPI::publish_archived_listings_chunked) but 
+    /// stops after the request currently in flight when the token is cancelled, returning the 
+    /// results of the requests completed so far along with [`Error::Cancelled`].
+    pub async fn publish_archived_listings_chunked_cancellable<'a, T>(
+        &self,
+        listing_ids: &'a [T],
+        cancel: &CancellationToken,
+    ) -> (Vec<response::listing::archived_listing::Result<'a>>, Option<Error>)
+    where
+        T: Borrow<String>,
+    {
+        helpers::submit_chunked(
+            listing_ids,
+            // Each archived listing is sent in its own request
+            1,
+            !self.dry_run,
+            self.batch_retry_policy.as_ref(),
+            cancel,
+            |chunk| self.publish_archived_listings_refs(chunk),
+            |result| matches!(result, Err(error) if error.kind().is_retryable()),
+        ).await
+    }
+    
+    /// Bulk updates any number of listings in the archive. This is a convenience method which 
+    /// handles mass updating of archived listings, where each listing is updated with its own 
+    /// request and requests are rate limited to a certain number per minute. If an error occurs, 
+    /// execution will cease and an error will be added to the return value. Note that any type 
+    /// can be used for the currencies parameter as long as it implements [`Serialize`].
+    /// 
+    /// If a [`BatchRetryPolicy`] is set, listings which failed with a retryable error are 
+    /// resubmitted after the other listings. Results are in the same order as the input listings.
+    pub async fn update_archived_listings_chunked<'a, T>(
+        &self,
+        listings: &'a [request::UpdateListing<T>],
+    ) -> (Vec<response::listing::update_listing::Result<'a, T>>, Option<Error>)
+    where
+        T: Serialize,
+    {
+        self.update_archived_listings_chunked_cancellable(listings, &CancellationToken::new()).await
+    }
+    
+    /// Bulk updates any number of listings in the archive. Same as 
+    /// [update_archived_listings_chunked](BackpackAPI::update_archived_listings_chunked) but 
+    /// stops after the request currently in flight when the token is cancelled, returning the 
+    /// results of the requests completed so far along with [`Error::Cancelled`].
+    pub async fn update_archived_listings_chunked_cancellable<'a, T>(
+        &self,
+        listings: &'a [request::UpdateListing<T>],
+        cancel: &CancellationToken,
+    ) -> (Vec<response::listing::update_listing::Result<'a, T>>, Option<Error>)
+    where
+        T: Serialize,
+    {
+        // Validate every listing up front so that no request is sent if any listing is invalid
+        if let Err(error) = self.check_valid(listings.iter().map(|listing| listing.validate())) {
+            return (Vec::new(), Some(error));
+        }
+        
+        helpers::submit_chunked(
+            listings,
+            // Each archived listing is sent in its own request
+            1,
+            !self.dry_run,
+            self.batch_retry_policy.as_ref(),
+            cancel,
+            |chunk| self.update_archived_listings_refs(chunk),
+            |result| matches!(result, Err(error) if error.kind().is_retryable()),
+        ).await
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Token<'a> {
     token: &'a str,
-}
\ No newline at end of file
+}
+
+/// Converts the results of requests for archived listings into per-listing results.
+async fn into_archived_listing_results<'a, T>(
+    results: Vec<(&'a T, Result<(), Error>)>,
+) -> Vec<response::listing::archived_listing::Result<'a>>
+where
+    T: Borrow<String>,
+{
+    let mut mapped = Vec::with_capacity(results.len());
+    
+    for (id, result) in results {
+        let id = id.borrow().as_str();
+        
+        mapped.push(match result {
+            Ok(()) => Ok(id),
+            Err(error) => Err(response::listing::archived_listing::ErrorListing {
+                message: helpers::error_message(error).await,
+                id,
+            }),
+        });
+    }
+    
+    mapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tf2_price::{Currencies, ref_to_weps};
+    use crate::api::mock::{mock_api, mock_response};
+    use crate::response::listing::ListingErrorKind;
+    
+    fn dry_run_api() -> BackpackAPI {
+        BackpackAPI::builder()
+            .token("token".into())
+            .dry_run(true)
+            .build()
+    }
+    
+    #[test]
+    fn dry_run_create_listings_returns_request() {
+        let backpacktf = dry_run_api();
+        let listings = [request::CreateListing::Sell {
+            id: 7764221391,
+            currencies: Currencies {
+                keys: 1,
+                weapons: ref_to_weps!(0.33),
+            },
+            details: None,
+            buyout: true,
+            offers: true,
+        }];
+        let results = async_std::task::block_on(backpacktf.create_listings(&listings)).unwrap();
+        let requests = backpacktf.take_dry_run_requests();
+        let request = requests.first().unwrap();
+        
+        assert!(matches!(results.as_slice(), [Err(error)] if error.kind() == ListingErrorKind::DryRun));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.uri, "https://api.backpack.tf/api/v2/classifieds/listings/batch");
+        assert_eq!(request.query, Some(json!({ "token": "[redacted]" })));
+        assert_eq!(request.body, Some(json!([{
+            "intent": "sell",
+            "id": "7764221391",
+            "currencies": {
+                "keys": 1,
+                "metal": 0.33
+            },
+            "buyout": true,
+            "offers": true
+        }])));
+        assert!(backpacktf.dry_run_requests().is_empty());
+    }
+    
+    #[test]
+    fn dry_run_chunked_collects_every_chunk() {
+        let backpacktf = dry_run_api();
+        let listing_ids = (0..250)
+            .map(|i| format!("440_{i}"))
+            .collect::<Vec<_>>();
+        let (deleted, error) = async_std::task::block_on(backpacktf.delete_listings_chunked(&listing_ids));
+        
+        assert_eq!(deleted, 0);
+        assert!(error.is_none());
+        assert_eq!(backpacktf.dry_run_requests().len(), 3);
+    }
+    
+    #[test]
+    fn dry_run_reports_every_listing_as_not_sent() {
+        let backpacktf = dry_run_api();
+        let currencies = Currencies {
+            keys: 1,
+            weapons: 0,
+        };
+        let listings = (0..150)
+            .map(|id| request::UpdateListing {
+                id: format!("440_{id}"),
+                currencies,
+                details: None,
+            })
+            .collect::<Vec<_>>();
+        let (results, error) = async_std::task::block_on(backpacktf.update_listings_chunked(&listings));
+        
+        assert!(error.is_none());
+        assert_eq!(results.len(), listings.len());
+        assert!(results.iter().all(|result| matches!(result, Err(error) if error.kind() == ListingErrorKind::DryRun)));
+        
+        let results = async_std::task::block_on(backpacktf.update_archived_listings(&listings[..2])).unwrap();
+        
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| matches!(result, Err(error) if error.kind() == ListingErrorKind::DryRun)));
+        assert!(matches!(
+            async_std::task::block_on(backpacktf.delete_listing("440_1")),
+            Err(Error::DryRun(request)) if request.method == Method::DELETE
+        ));
+        assert!(matches!(
+            async_std::task::block_on(backpacktf.update_listing("440_1", None, &currencies)),
+            Err(Error::DryRun(request)) if request.method == Method::PATCH
+        ));
+    }
+    
+    #[test]
+    fn dry_run_delete_archived_listings_records_each_chunk() {
+        let backpacktf = dry_run_api();
+        let listing_ids = (0..150)
+            .map(|i| format!("440_{i}"))
+            .collect::<Vec<_>>();
+        let (deleted, error) = async_std::task::block_on(backpacktf.delete_archived_listings_chunked(&listing_ids));
+        let requests = backpacktf.dry_run_requests();
+        
+        // None of the listings were deleted
+        assert_eq!(deleted, 0);
+        assert!(error.is_none());
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, Method::DELETE);
+        assert_eq!(requests[0].uri, "https://api.backpack.tf/api/v2/classifieds/archive/batch");
+        assert_eq!(requests[1].body, Some(json!({ "listing_ids": listing_ids[100..] })));
+    }
+    
+    #[test]
+    fn dry_run_publish_archived_listings_publishes_each_listing() {
+        let backpacktf = dry_run_api();
+        let listing_ids = ["440_1".to_string(), "440_2".to_string()];
+        let results = async_std::task::block_on(backpacktf.publish_archived_listings(&listing_ids)).unwrap();
+        let requests = backpacktf.dry_run_requests();
+        let uris = requests
+            .iter()
+            .map(|request| request.uri.as_str())
+            .collect::<Vec<_>>();
+        
+        assert!(results.iter().all(|result| matches!(result, Err(error) if error.kind() == ListingErrorKind::DryRun)));
+        assert_eq!(uris, vec![
+            "https://api.backpack.tf/api/v2/classifieds/archive/440_1/publish",
+            "https://api.backpack.tf/api/v2/classifieds/archive/440_2/publish",
+        ]);
+    }
+    
+    #[test]
+    fn publish_archived_listings_keeps_server_messages() {
+        let backpacktf = mock_api(|request| {
+            if request.url().path().contains("440_2") {
+                mock_response(404, r#"{"message":"Listing not found"}"#)
+            } else {
+                mock_response(200, "{}")
+            }
+        });
+        let listing_ids = ["440_1".to_string(), "440_2".to_string(), "440_3".to_string()];
+        let results = async_std::task::block_on(backpacktf.publish_archived_listings(&listing_ids)).unwrap();
+        
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], Ok("440_1"));
+        assert_eq!(results[2], Ok("440_3"));
+        
+        let error = results[1].as_ref().unwrap_err();
+        
+        assert_eq!(error.id, "440_2");
+        assert_eq!(error.message, "Listing not found");
+        assert_eq!(error.kind(), response::listing::ListingErrorKind::ListingNotFound);
+    }
+    
+    #[test]
+    fn archived_listing_requests_wait_for_retry_after() {
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let backpacktf = mock_api({
+            let attempts = Arc::clone(&attempts);
+            
+            move |request| {
+                let mut attempts = attempts.lock().unwrap();
+                let path = request.url().path().to_string();
+                let rate_limited = path.contains("440_1") && !attempts.contains(&path);
+                
+                attempts.push(path);
+                
+                if rate_limited {
+                    let mut response = mock_response(429, r#"{"message":"Too many requests"}"#);
+                    
+                    response.headers_mut().insert("Retry-After", "0".parse().unwrap());
+                    response
+                } else {
+                    mock_response(200, "{}")
+                }
+            }
+        });
+        let listing_ids = ["440_1".to_string(), "440_2".to_string()];
+        let (results, error) = async_std::task::block_on(backpacktf.publish_archived_listings_chunked(&listing_ids));
+        
+        assert!(results.iter().all(Result::is_ok));
+        assert!(error.is_none());
+        assert_eq!(*attempts.lock().unwrap(), vec![
+            "/api/v2/classifieds/archive/440_1/publish",
+            "/api/v2/classifieds/archive/440_1/publish",
+            "/api/v2/classifieds/archive/440_2/publish",
+        ]);
+    }
+    
+    #[test]
+    fn delete_archived_listings_sends_batch_request() {
+        let backpacktf = mock_api(|request| {
+            let body = request.body()
+                .and_then(|body| body.as_bytes())
+                .and_then(|body| serde_json::from_slice::<serde_json::Value>(body).ok());
+            
+            assert_eq!(request.method(), Method::DELETE);
+            assert_eq!(request.url().path(), "/api/v2/classifieds/archive/batch");
+            assert_eq!(request.url().query(), Some("token=token"));
+            assert_eq!(body, Some(json!({ "listing_ids": ["440_1", "440_2", "440_3"] })));
+            mock_response(200, r#"{"deleted":2,"skipped":[],"errors":[]}"#)
+        });
+        let listing_ids = ["440_1".to_string(), "440_2".to_string(), "440_3".to_string()];
+        let deleted = async_std::task::block_on(backpacktf.delete_archived_listings(&listing_ids)).unwrap();
+        
+        assert_eq!(deleted, 2);
+        
+        let backpacktf = mock_api(|_request| mock_response(400, r#"{"message":"Bad request"}"#));
+        let error = async_std::task::block_on(backpacktf.delete_archived_listings(&listing_ids)).unwrap_err();
+        
+        assert!(matches!(error, Error::Http(response) if response.status() == 400));
+    }
+    
+    #[test]
+    fn archived_listings_batch_is_limited() {
+        let backpacktf = dry_run_api();
+        let listing_ids = (0..101)
+            .map(|i| format!("440_{i}"))
+            .collect::<Vec<_>>();
+        let error = async_std::task::block_on(backpacktf.delete_archived_listings(&listing_ids)).unwrap_err();
+        
+        assert!(matches!(error, Error::Parameter(ParameterError::MaximumLengthExceeded { max: 100, .. })));
+    }
+    
+    #[test]
+    fn validate_listings_rejects_invalid_listing() {
+        let backpacktf = BackpackAPI::builder()
+            .token("token".into())
+            .dry_run(true)
+            .validate_listings(true)
+            .build();
+        let listings = (0..150)
+            .map(|id| request::UpdateListing {
+                id: if id == 120 { String::new() } else { format!("440_{id}") },
+                currencies: Currencies {
+                    keys: 1,
+                    weapons: 0,
+                },
+                details: None,
+            })
+            .collect::<Vec<_>>();
+        let (results, error) = async_std::task::block_on(backpacktf.update_listings_chunked(&listings));
+        
+        assert!(results.is_empty());
+        assert!(matches!(
+            error,
+            Some(Error::Parameter(ParameterError::InvalidListing { index: 120, issues }))
+            if issues == vec![request::ValidationIssue::EmptyListingId]
+        ));
+    }
+}
@@ -1,10 +1,18 @@
+use crate::{BatchRetryPolicy, CancellationToken};
 use crate::error::Error;
+use std::future::Future;
 use std::time::{Instant, Duration};
 use serde::Deserialize;
 use serde::de::DeserializeOwned;
 use reqwest::{header::RETRY_AFTER, StatusCode};
 use log::error;
 
+/// The body of an error response.
+#[derive(Deserialize, Debug)]
+struct ErrorResponse {
+    message: String,
+}
+
 /// Handles rate limits for requests that are split into chunks.
 pub struct Cooldown<'a, T> {
     start_time: Instant,
@@ -12,7 +20,15 @@ pub struct Cooldown<'a, T> {
     cooldown_counter: usize,
     limit: usize,
     cooldown: u64,
-    chunks: std::slice::Chunks<'a, T>,
+    chunk_size: usize,
+    data: &'a [T],
+}
+
+/// The rate limit state of a [`Cooldown`], used to carry the limit over to another set of data.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    start_time: Instant,
+    cooldown_counter: usize,
 }
 
 impl<'a, T> Cooldown<'a, T> 
@@ -28,7 +44,46 @@ where
             cooldown_counter: 0,
             limit: 10,
             cooldown: 60,
-            chunks: data.chunks(100),
+            chunk_size: 100,
+            data,
+        }
+    }
+    
+    /// Creates a cooldown for the data which continues from the rate limit of a previous 
+    /// cooldown.
+    pub fn with_rate_limit(
+        data: &'a [T],
+        rate_limit: RateLimit,
+    ) -> Self {
+        Self {
+            start_time: rate_limit.start_time,
+            cooldown_counter: rate_limit.cooldown_counter,
+            ..Self::new(data)
+        }
+    }
+    
+    /// Sets the number of items in each chunk. Each chunk counts as one request towards the rate 
+    /// limit.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+    
+    /// Sets whether chunks are rate limited. When not rate limited, chunks never wait for the 
+    /// cooldown.
+    pub fn rate_limited(mut self, rate_limited: bool) -> Self {
+        if !rate_limited {
+            self.limit = usize::MAX;
+        }
+        
+        self
+    }
+    
+    /// Gets the current rate limit state.
+    pub fn rate_limit(&self) -> RateLimit {
+        RateLimit {
+            start_time: self.start_time,
+            cooldown_counter: self.cooldown_counter,
         }
     }
     
@@ -43,33 +98,38 @@ where
     }
     
     pub fn next(&mut self) -> Option<(&'a [T], Option<Duration>)> {
-        if let Some(chunk) = self.chunks.nth(self.i) {
-            self.i += 1;
-            self.cooldown_counter += 1;
+        let start = self.i * self.chunk_size;
             
-            // we can skip the wait
-            if 
-                // if we have reached the end
-                self.i == self.chunks.len() ||
-                // or the current chunk index is under the limit
-                self.cooldown_counter <= self.limit
-            {
-                Some((chunk, None))
+        if start >= self.data.len() {
+            return None;
+        }
+        
+        let end = (start + self.chunk_size).min(self.data.len());
+        let chunk = &self.data[start..end];
+        
+        self.i += 1;
+        self.cooldown_counter += 1;
+        
+        // we can skip the wait
+        if 
+            // if we have reached the end
+            end == self.data.len() ||
+            // or the current chunk index is under the limit
+            self.cooldown_counter <= self.limit
+        {
+            Some((chunk, None))
+        } else {
+            let elapsed = self.start_time.elapsed().as_secs() + 1;
+            let wait = if elapsed > self.cooldown {
+                0
             } else {
-                let elapsed = self.start_time.elapsed().as_secs() + 1;
-                let wait = if elapsed > self.cooldown {
-                    0
-                } else {
-                    self.cooldown - elapsed
-                };
+                self.cooldown - elapsed
+            };
                 
-                self.start_time = Instant::now();
-                self.cooldown_counter = 0;
+            self.start_time = Instant::now();
+            self.cooldown_counter = 0;
                 
-                Some((chunk, Some(Duration::from_secs(wait))))
-            }
-        } else {
-            None
+            Some((chunk, Some(Duration::from_secs(wait))))
         }
     }
 }
@@ -101,15 +161,188 @@ pub fn retryable_duration(error: &Error) -> Option<Duration> {
     None
 }
 
-pub async fn parses_response<D>(response: reqwest::Response) -> Result<D, Error>
+/// Sleeps for the given duration, waking early if the token is cancelled.
+pub async fn sleep_cancellable(
+    duration: Duration,
+    cancel: &CancellationToken,
+) {
+    let _ = async_std::future::timeout(duration, cancel.cancelled()).await;
+}
+
+/// Submits queries in chunks of `chunk_size` using `submit`, which must return one result per 
+/// query in the same order as the queries it was given. Chunks are rate limited when 
+/// `rate_limited` is set. If a retry policy is given, queries with 
+/// results matching `is_retryable` are resubmitted until they succeed or the policy's retry 
+/// budget is spent.
+/// 
+/// Results are returned in the same order as `queries`. If an error occurs or the token is 
+/// cancelled, queries which were never submitted are omitted from the results.
+pub async fn submit_chunked<'a, Q, R, F, Fut, P>(
+    queries: &'a [Q],
+    chunk_size: usize,
+    rate_limited: bool,
+    retry_policy: Option<&BatchRetryPolicy>,
+    cancel: &CancellationToken,
+    submit: F,
+    is_retryable: P,
+) -> (Vec<R>, Option<Error>)
 where
-    D: DeserializeOwned
+    F: Fn(Vec<&'a Q>) -> Fut,
+    Fut: Future<Output = Result<Vec<R>, Error>>,
+    P: Fn(&R) -> bool,
+{
+    let mut results = std::iter::repeat_with(|| None)
+        .take(queries.len())
+        .collect::<Vec<Option<R>>>();
+    let mut pending = (0..queries.len()).collect::<Vec<_>>();
+    let mut retries = 0;
+    let mut rate_limit = None;
+    
+    loop {
+        // Each round continues from the rate limit of the previous round so retries can't 
+        // exceed it
+        let chunked = match rate_limit {
+            Some(rate_limit) => Cooldown::with_rate_limit(&pending, rate_limit),
+            None => Cooldown::new(&pending),
+        };
+        let mut chunked = chunked
+            .chunk_size(chunk_size)
+            .rate_limited(rate_limited);
+        
+        while let Some((indices, duration)) = chunked.next() {
+            if cancel.is_cancelled() {
+                return (results.into_iter().flatten().collect(), Some(Error::Cancelled));
+            }
+            
+            let chunk = indices
+                .iter()
+                .map(|index| &queries[*index])
+                .collect::<Vec<_>>();
+            
+            match submit(chunk).await {
+                Ok(chunk_results) => {
+                    for (index, result) in indices.iter().zip(chunk_results) {
+                        results[*index] = Some(result);
+                    }
+                    
+                    if let Some(duration) = duration {
+                        sleep_cancellable(duration, cancel).await;
+                    }
+                },
+                Err(error) => {
+                    if let Some(duration) = retryable_duration(&error) {
+                        sleep_cancellable(duration, cancel).await;
+                        chunked.go_back();
+                        continue;
+                    }
+                    
+                    return (results.into_iter().flatten().collect(), Some(error));
+                },
+            }
+        }
+        
+        rate_limit = Some(chunked.rate_limit());
+        
+        let Some(retry_policy) = retry_policy else {
+            break;
+        };
+        
+        if retries >= retry_policy.max_retries {
+            break;
+        }
+        
+        pending.retain(|index| results[*index].as_ref().is_some_and(&is_retryable));
+        
+        if pending.is_empty() {
+            break;
+        }
+        
+        retries += 1;
+        sleep_cancellable(retry_policy.backoff(retries), cancel).await;
+    }
+    
+    (results.into_iter().flatten().collect(), None)
+}
+
+/// Sends a request for each query in a chunk using `send`, returning the result for each query 
+/// in the same order. When `rate_limited` is set, each request counts towards the rate limit. 
+/// Requests which are rate limited by the server are retried after the time given in the 
+/// response. If the token is cancelled, queries which were never sent are omitted from the 
+/// results.
+pub async fn submit_each<'a, Q, S, F, Fut>(
+    chunk: Vec<&'a Q>,
+    rate_limited: bool,
+    cancel: &CancellationToken,
+    send: F,
+) -> Vec<(&'a Q, Result<S, Error>)>
+where
+    Q: ?Sized,
+    F: Fn(&'a Q) -> Fut,
+    Fut: Future<Output = Result<S, Error>>,
 {
-    #[derive(Deserialize, Debug)]
-    struct ErrorResponse {
-        message: String,
+    let mut results = Vec::with_capacity(chunk.len());
+    let mut requests = Cooldown::new(&chunk)
+        .chunk_size(1)
+        .rate_limited(rate_limited);
+    
+    while let Some((queries, duration)) = requests.next() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        
+        let query = queries[0];
+        
+        match send(query).await {
+            Err(error) => {
+                if let Some(duration) = retryable_duration(&error) {
+                    sleep_cancellable(duration, cancel).await;
+                    requests.go_back();
+                    continue;
+                }
+                
+                results.push((query, Err(error)));
+            },
+            result => results.push((query, result)),
+        }
+        
+        if let Some(duration) = duration {
+            sleep_cancellable(duration, cancel).await;
+        }
     }
     
+    results
+}
+
+/// Gets the message of an error. The message of an HTTP error is read from the response body 
+/// when it has one so that the server's reason is not lost.
+pub async fn error_message(error: Error) -> String {
+    let message = error.to_string();
+    let Error::Http(response) = error else {
+        return message;
+    };
+    
+    match response.bytes().await {
+        Ok(body) => serde_json::from_slice::<ErrorResponse>(&body)
+            .map(|error_body| error_body.message)
+            .unwrap_or(message),
+        Err(_) => message,
+    }
+}
+
+/// Checks that the response has a successful status code, ignoring the body.
+#[allow(clippy::result_large_err)]
+pub fn check_response(response: reqwest::Response) -> Result<(), Error> {
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(Error::Http(response))
+    }
+}
+
+pub async fn parses_response<D>(response: reqwest::Response) -> Result<D, Error>
+where
+    D: DeserializeOwned
+{
     let status = response.status();
     
     match status.as_u16() {
@@ -167,4 +400,168 @@ mod tests {
         // it resets, there should now be no duration
         assert!(duration.is_none());
     }
+    
+    #[test]
+    fn chunk_size_counts_each_chunk_as_a_request() {
+        let vec = (0..20).collect::<Vec<_>>();
+        let mut cooldown = Cooldown::new(&vec).chunk_size(1);
+        
+        for i in 0..10 {
+            let (chunk, duration) = cooldown.next().unwrap();
+            
+            assert_eq!(chunk, &[i]);
+            assert!(duration.is_none());
+        }
+        
+        let (chunk, duration) = cooldown.next().unwrap();
+        
+        // the 11th request waits for the cooldown
+        assert_eq!(chunk, &[10]);
+        assert!(duration.is_some());
+    }
+    
+    #[test]
+    fn submit_each_returns_results_in_order() {
+        let queries = [1, 2, 3];
+        let results = async_std::task::block_on(submit_each(
+            queries.iter().collect(),
+            true,
+            &CancellationToken::new(),
+            |query: &u32| async move {
+                if *query == 2 {
+                    Err(Error::Response("sus".into()))
+                } else {
+                    Ok(*query * 10)
+                }
+            },
+        ));
+        
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[0], (1, Ok(10))));
+        assert!(matches!(results[1], (2, Err(Error::Response(_)))));
+        assert!(matches!(results[2], (3, Ok(30))));
+    }
+    
+    #[test]
+    fn submit_each_keeps_results_after_an_error() {
+        let queries = [1, 2, 3];
+        let results = async_std::task::block_on(submit_each(
+            queries.iter().collect(),
+            true,
+            &CancellationToken::new(),
+            |query: &u32| async move {
+                if *query == 1 {
+                    Ok(())
+                } else {
+                    Err(Error::Parameter(crate::error::ParameterError::MissingToken))
+                }
+            },
+        ));
+        
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[0], (1, Ok(()))));
+        assert!(matches!(results[1], (2, Err(Error::Parameter(_)))));
+    }
+    
+    #[test]
+    fn submit_chunked_retries_retryable_results() {
+        let queries = (0..250).collect::<Vec<u32>>();
+        let attempts = std::sync::Mutex::new(std::collections::HashMap::<u32, u32>::new());
+        let retry_policy = BatchRetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        };
+        let (results, error) = async_std::task::block_on(submit_chunked(
+            &queries,
+            100,
+            true,
+            Some(&retry_policy),
+            &CancellationToken::new(),
+            |chunk| {
+                let results = chunk
+                    .into_iter()
+                    .map(|query| {
+                        let mut attempts = attempts.lock().unwrap();
+                        let attempt = attempts.entry(*query).or_default();
+                        
+                        *attempt += 1;
+                        
+                        // every tenth query succeeds on the second attempt
+                        // every fiftieth query never succeeds
+                        if query % 50 == 0 || (query % 10 == 0 && *attempt < 2) {
+                            Err(*query)
+                        } else {
+                            Ok(*query)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                
+                async move { Ok(results) }
+            },
+            |result: &Result<u32, u32>| result.is_err(),
+        ));
+        let attempts = attempts.into_inner().unwrap();
+        
+        assert!(error.is_none());
+        assert_eq!(results.len(), queries.len());
+        assert!(results.iter().zip(&queries).all(|(result, query)| match result {
+            Ok(value) | Err(value) => value == query,
+        }));
+        assert_eq!(results[10], Ok(10));
+        assert_eq!(results[50], Err(50));
+        assert_eq!(attempts[&10], 2);
+        assert_eq!(attempts[&50], 3);
+        assert_eq!(attempts[&11], 1);
+    }
+    
+    #[test]
+    fn next_visits_every_chunk_in_order() {
+        let vec = (0..550).collect::<Vec<_>>();
+        let mut cooldown = Cooldown::new(&vec);
+        let mut firsts = Vec::new();
+        
+        while let Some((chunk, _)) = cooldown.next() {
+            firsts.push(chunk[0]);
+        }
+        
+        assert_eq!(firsts, vec![0, 100, 200, 300, 400, 500]);
+    }
+    
+    #[test]
+    fn with_rate_limit_continues_previous_limit() {
+        let first = (0..1000).collect::<Vec<_>>();
+        let second = (0..200).collect::<Vec<_>>();
+        let mut cooldown = Cooldown::new(&first);
+        
+        while cooldown.next().is_some() {}
+        
+        let (_, duration) = Cooldown::new(&second).next().unwrap();
+        
+        // a new cooldown starts with a fresh limit
+        assert!(duration.is_none());
+        
+        let (_, duration) = Cooldown::with_rate_limit(&second, cooldown.rate_limit()).next().unwrap();
+        
+        // the 11th chunk must wait
+        assert!(duration.is_some());
+    }
+    
+    #[test]
+    fn go_back_repeats_chunk() {
+        let vec = (0..300).collect::<Vec<_>>();
+        let mut cooldown = Cooldown::new(&vec);
+        
+        cooldown.next();
+        
+        let (chunk, _) = cooldown.next().unwrap();
+        
+        assert_eq!(chunk[0], 100);
+        
+        cooldown.go_back();
+        
+        let (chunk, _) = cooldown.next().unwrap();
+        
+        assert_eq!(chunk[0], 100);
+    }
 }
\ No newline at end of file
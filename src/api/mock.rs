@@ -0,0 +1,45 @@
+//! Mock clients for testing requests without sending them.
+
+use crate::BackpackAPI;
+
+type MockResponse<'a> = std::pin::Pin<Box<dyn std::future::Future<Output = reqwest_middleware::Result<reqwest::Response>> + Send + 'a>>;
+
+/// Creates a client which answers every request using `respond` instead of sending it.
+pub fn mock_api<F>(respond: F) -> BackpackAPI
+where
+    F: Fn(&reqwest::Request) -> http::Response<String> + Send + Sync + 'static,
+{
+    BackpackAPI::builder()
+        .token("token".into())
+        .client(mock_client(respond))
+        .build()
+}
+
+/// Creates an HTTP client which answers every request using `respond` instead of sending it.
+pub fn mock_client<F>(respond: F) -> reqwest_middleware::ClientWithMiddleware
+where
+    F: Fn(&reqwest::Request) -> http::Response<String> + Send + Sync + 'static,
+{
+    fn middleware<M>(middleware: M) -> M
+    where
+        M: for<'a> Fn(reqwest::Request, &'a mut http::Extensions, reqwest_middleware::Next<'a>) -> MockResponse<'a>,
+    {
+        middleware
+    }
+    
+    reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(middleware(move |request, _extensions, _next| {
+            let response = reqwest::Response::from(respond(&request));
+            
+            Box::pin(async move { Ok(response) })
+        }))
+        .build()
+}
+
+/// Creates a response with the given status and body.
+pub fn mock_response(status: u16, body: &str) -> http::Response<String> {
+    http::Response::builder()
+        .status(status)
+        .body(body.into())
+        .unwrap()
+}
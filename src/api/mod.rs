@@ -1,5 +1,7 @@
 mod backpack_api;
-mod helpers;
+pub(crate) mod helpers;
 mod api_response;
+#[cfg(test)]
+pub(crate) mod mock;
 
 pub use backpack_api::BackpackAPI;
\ No newline at end of file
@@ -0,0 +1,426 @@
+//! Automatic bumping of listings.
+
+use crate::{BackpackAPI, CancellationToken};
+use crate::api::helpers::sleep_cancellable;
+use crate::error::{Error, TryFromListingError};
+use crate::request::CreateListing;
+use crate::response::classifieds_limits::ClassifiedsLimits;
+use crate::response::listing::{Listing, ListingErrorKind, Status};
+use std::time::Duration;
+use async_std::channel::{self, Receiver, Sender};
+use chrono::Utc;
+use tf2_price::Currencies;
+
+/// Options for an [`AutoBumper`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoBumpOptions {
+    /// How long after being bumped a listing is due for another bump. Passed to
+    /// [`Listing::relistable`].
+    pub bump_interval: Duration,
+    /// How long to wait between checking for listings which are due.
+    pub check_interval: Duration,
+    /// The maximum number of listings bumped in a single cycle. Listings are re-created in
+    /// chunks of 100 which are rate limited, so this bounds how long a cycle takes.
+    pub max_bumps_per_cycle: usize,
+}
+
+impl Default for AutoBumpOptions {
+    fn default() -> Self {
+        Self {
+            bump_interval: Duration::from_secs(30 * 60),
+            check_interval: Duration::from_secs(5 * 60),
+            max_bumps_per_cycle: 200,
+        }
+    }
+}
+
+/// The reason a listing which was due for a bump was not bumped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BumpSkipReason {
+    /// The listing is hidden because the user does not have enough currency.
+    NotEnoughCurrency,
+    /// The listing is hidden by the user.
+    HiddenByUser,
+    /// The listing's currencies are cash or include a hat value.
+    UnsupportedCurrencies,
+    /// The listing's item could not be converted into a listing query.
+    UnsupportedItem,
+    /// The listing is beyond the total number of listing slots in the account's
+    /// [`ClassifiedsLimits`].
+    ListingLimit,
+    /// The maximum number of bumps for this cycle was reached.
+    CycleBudgetExhausted,
+}
+
+/// An event emitted by an [`AutoBumper`].
+#[derive(Debug)]
+pub enum AutoBumpEvent {
+    /// A cycle started.
+    CycleStarted {
+        /// The number of listings past their bump interval.
+        due: usize,
+    },
+    /// A listing was bumped.
+    Bumped {
+        /// The ID of the listing.
+        listing_id: String,
+        /// How long it had been since the listing was bumped.
+        age: Duration,
+    },
+    /// A listing which was due was not bumped.
+    Skipped {
+        /// The ID of the listing.
+        listing_id: String,
+        /// The reason the listing was skipped.
+        reason: BumpSkipReason,
+    },
+    /// Re-creating a listing failed.
+    Failed {
+        /// The ID of the listing.
+        listing_id: String,
+        /// The kind of error.
+        kind: ListingErrorKind,
+        /// The error message in the response.
+        message: String,
+    },
+    /// An error stopped the cycle before it completed.
+    Error(Error),
+    /// A cycle completed.
+    CycleCompleted {
+        /// The number of listings bumped.
+        bumped: usize,
+        /// The number of listings skipped.
+        skipped: usize,
+        /// The number of listings which failed to be re-created.
+        failed: usize,
+    },
+}
+
+/// Keeps an account's listings fresh by periodically re-creating listings which are past their
+/// bump interval.
+///
+/// Each cycle fetches the account's [`ClassifiedsLimits`] and active listings, then re-creates
+/// listings which are [relistable](Listing::relistable) in priority order, starting with the
+/// listings which were bumped the longest time ago. No more listings are re-created than the
+/// account has listing slots. Listings with a [`Status`] of
+/// [`NotEnoughCurrency`](Status::NotEnoughCurrency) or [`HiddenByUser`](Status::HiddenByUser)
+/// are skipped. When the client is in dry-run mode, listings whose requests were recorded are
+/// reported as bumped.
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::{AutoBumper, AutoBumpOptions, BackpackAPI, CancellationToken};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let backpacktf = BackpackAPI::builder()
+///         .token("token".into())
+///         .build();
+///     let cancel = CancellationToken::new();
+///     let events = AutoBumper::new(backpacktf, AutoBumpOptions::default()).spawn(cancel.clone());
+///
+///     while let Ok(event) = events.recv().await {
+///         println!("{event:?}");
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AutoBumper {
+    api: BackpackAPI,
+    options: AutoBumpOptions,
+}
+
+impl AutoBumper {
+    /// Creates a new auto-bumper.
+    pub fn new(
+        api: BackpackAPI,
+        options: AutoBumpOptions,
+    ) -> Self {
+        Self {
+            api,
+            options,
+        }
+    }
+    
+    /// Spawns a background task which runs a cycle every
+    /// [`check_interval`](AutoBumpOptions::check_interval) until the token is cancelled or the
+    /// receiver is dropped. Events are sent to the returned receiver as they happen.
+    pub fn spawn(
+        self,
+        cancel: CancellationToken,
+    ) -> Receiver<AutoBumpEvent> {
+        let (sender, receiver) = channel::unbounded();
+        
+        async_std::task::spawn(async move {
+            while !cancel.is_cancelled() && !sender.is_closed() {
+                self.run_cycle(&sender, &cancel).await;
+                sleep_cancellable(self.options.check_interval, &cancel).await;
+            }
+        });
+        
+        receiver
+    }
+    
+    /// Runs a single cycle, returning the events emitted.
+    pub async fn bump_once(&self) -> Vec<AutoBumpEvent> {
+        let (sender, receiver) = channel::unbounded();
+        
+        self.run_cycle(&sender, &CancellationToken::new()).await;
+        drop(sender);
+        
+        let mut events = Vec::new();
+        
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        
+        events
+    }
+    
+    /// Runs a single cycle, sending events to `sender`.
+    async fn run_cycle(
+        &self,
+        sender: &Sender<AutoBumpEvent>,
+        cancel: &CancellationToken,
+    ) {
+        let emit = |event| {
+            // Errors only occur when the receiver is dropped, which stops the scheduler
+            let _ = sender.try_send(event);
+        };
+        let limits = match self.api.classifieds_limits().await {
+            Ok(limits) => limits,
+            Err(error) => return emit(AutoBumpEvent::Error(error)),
+        };
+        let (listings, error) = self.api.get_all_listings_cancellable(cancel).await;
+        
+        if let Some(error) = error {
+            return emit(AutoBumpEvent::Error(error));
+        }
+        
+        let plan = plan_bumps(&listings, &limits, &self.options);
+        let mut skipped = plan.skipped.len();
+        let mut bumped = 0;
+        let mut failed = 0;
+        
+        emit(AutoBumpEvent::CycleStarted {
+            due: plan.due,
+        });
+        
+        for (listing_id, reason) in plan.skipped {
+            emit(AutoBumpEvent::Skipped {
+                listing_id,
+                reason,
+            });
+        }
+        
+        let queries = plan.bumps
+            .iter()
+            .map(|bump| bump.query.clone())
+            .collect::<Vec<_>>();
+        let (results, error) = self.api.create_listings_chunked_cancellable(&queries, cancel).await;
+        
+        for (bump, result) in plan.bumps.iter().zip(results) {
+            match result {
+                // In dry-run mode the request which would have re-created the listing is recorded
+                Err(error) if error.kind() != ListingErrorKind::DryRun => {
+                    failed += 1;
+                    emit(AutoBumpEvent::Failed {
+                        listing_id: bump.listing_id.clone(),
+                        kind: error.kind(),
+                        message: error.message,
+                    });
+                },
+                _ => {
+                    bumped += 1;
+                    emit(AutoBumpEvent::Bumped {
+                        listing_id: bump.listing_id.clone(),
+                        age: bump.age,
+                    });
+                },
+            }
+        }
+        
+        if let Some(error) = error {
+            skipped += plan.bumps.len() - bumped - failed;
+            emit(AutoBumpEvent::Error(error));
+        }
+        
+        emit(AutoBumpEvent::CycleCompleted {
+            bumped,
+            skipped,
+            failed,
+        });
+    }
+}
+
+/// A listing to bump.
+#[derive(Debug)]
+struct Bump {
+    listing_id: String,
+    age: Duration,
+    query: CreateListing<Currencies>,
+}
+
+/// The bumps for a cycle.
+#[derive(Debug)]
+struct BumpPlan {
+    due: usize,
+    bumps: Vec<Bump>,
+    skipped: Vec<(String, BumpSkipReason)>,
+}
+
+/// Selects the listings to bump in priority order.
+fn plan_bumps(
+    listings: &[Listing],
+    limits: &ClassifiedsLimits,
+    options: &AutoBumpOptions,
+) -> BumpPlan {
+    let now = Utc::now();
+    let mut due = listings
+        .iter()
+        .filter(|listing| !listing.archived && listing.relistable(options.bump_interval))
+        .collect::<Vec<_>>();
+    let mut plan = BumpPlan {
+        due: due.len(),
+        bumps: Vec::new(),
+        skipped: Vec::new(),
+    };
+    
+    // Listings which were bumped the longest time ago are bumped first
+    due.sort_by_key(|listing| listing.bumped_at);
+    
+    for listing in due {
+        let reason = match listing.status {
+            Status::NotEnoughCurrency => Some(BumpSkipReason::NotEnoughCurrency),
+            Status::HiddenByUser => Some(BumpSkipReason::HiddenByUser),
+            _ if plan.bumps.len() >= limits.total as usize => Some(BumpSkipReason::ListingLimit),
+            _ if plan.bumps.len() >= options.max_bumps_per_cycle => Some(BumpSkipReason::CycleBudgetExhausted),
+            _ => None,
+        };
+        let query = match reason {
+            Some(reason) => Err(reason),
+            None => to_create_listing(listing),
+        };
+        
+        match query {
+            Ok(query) => plan.bumps.push(Bump {
+                listing_id: listing.id.clone(),
+                age: (now - listing.bumped_at).to_std().unwrap_or_default(),
+                query,
+            }),
+            Err(reason) => plan.skipped.push((listing.id.clone(), reason)),
+        }
+    }
+    
+    plan
+}
+
+/// Converts a listing into the query needed to re-create it.
+fn to_create_listing(listing: &Listing) -> Result<CreateListing<Currencies>, BumpSkipReason> {
+    CreateListing::try_from(listing).map_err(|error| match error {
+        TryFromListingError::Currencies(_) => BumpSkipReason::UnsupportedCurrencies,
+        TryFromListingError::NegativeDefindex(_) |
+        TryFromListingError::MissingItemId => BumpSkipReason::UnsupportedItem,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::listing::tests::get_listing;
+    use chrono::Duration as ChronoDuration;
+    
+    fn get_limits(total: u32) -> ClassifiedsLimits {
+        ClassifiedsLimits {
+            promotion_slots_available: 0,
+            used: 0,
+            total,
+            baseline: total,
+            donation_bonus: 0,
+            gifted_premium_months_bonus: 0,
+            multiplier: 1,
+            twitter_follower_bonus: 0,
+            accepted_suggestion_bonus: 0,
+            mvp_donation_bonus: 0,
+            group_membership_bonus: 0,
+        }
+    }
+    
+    fn bumped_listing(id: &str, minutes_ago: i64, status: Status) -> Listing {
+        let mut listing = get_listing(id);
+        
+        // Listings are bumped after they are listed
+        listing.listed_at = Utc::now() - ChronoDuration::days(1);
+        listing.bumped_at = Utc::now() - ChronoDuration::minutes(minutes_ago);
+        listing.status = status;
+        listing
+    }
+    
+    #[test]
+    fn plans_bumps_in_priority_order() {
+        let listings = [
+            bumped_listing("fresh", 5, Status::Active),
+            bumped_listing("old", 60, Status::Active),
+            bumped_listing("oldest", 120, Status::Active),
+            bumped_listing("hidden", 90, Status::HiddenByUser),
+            bumped_listing("poor", 90, Status::NotEnoughCurrency),
+        ];
+        let plan = plan_bumps(&listings, &get_limits(100), &AutoBumpOptions::default());
+        let bumped = plan.bumps
+            .iter()
+            .map(|bump| bump.listing_id.as_str())
+            .collect::<Vec<_>>();
+        
+        assert_eq!(plan.due, 4);
+        assert_eq!(bumped, vec!["oldest", "old"]);
+        assert!(plan.skipped.contains(&("hidden".into(), BumpSkipReason::HiddenByUser)));
+        assert!(plan.skipped.contains(&("poor".into(), BumpSkipReason::NotEnoughCurrency)));
+        assert!(matches!(
+            &plan.bumps[0].query,
+            CreateListing::Buy { item, .. } if item.defindex == 30998
+        ));
+    }
+    
+    #[test]
+    fn honours_cycle_budget() {
+        let listings = [
+            bumped_listing("a", 60, Status::Active),
+            bumped_listing("b", 70, Status::Active),
+            bumped_listing("c", 80, Status::Active),
+        ];
+        let options = AutoBumpOptions {
+            max_bumps_per_cycle: 1,
+            ..Default::default()
+        };
+        let plan = plan_bumps(&listings, &get_limits(100), &options);
+        
+        assert_eq!(plan.bumps.len(), 1);
+        assert_eq!(plan.bumps[0].listing_id, "c");
+        assert_eq!(plan.skipped, vec![
+            ("b".into(), BumpSkipReason::CycleBudgetExhausted),
+            ("a".into(), BumpSkipReason::CycleBudgetExhausted),
+        ]);
+        
+        let plan = plan_bumps(&listings, &get_limits(100), &AutoBumpOptions::default());
+        
+        assert_eq!(plan.bumps.len(), 3);
+        assert!(plan.skipped.is_empty());
+    }
+    
+    #[test]
+    fn honours_listing_limit() {
+        let listings = [
+            bumped_listing("a", 60, Status::Active),
+            bumped_listing("b", 70, Status::Active),
+            bumped_listing("c", 80, Status::Active),
+        ];
+        let plan = plan_bumps(&listings, &get_limits(2), &AutoBumpOptions::default());
+        let bumped = plan.bumps
+            .iter()
+            .map(|bump| bump.listing_id.as_str())
+            .collect::<Vec<_>>();
+        
+        assert_eq!(bumped, vec!["c", "b"]);
+        assert_eq!(plan.skipped, vec![("a".into(), BumpSkipReason::ListingLimit)]);
+    }
+}
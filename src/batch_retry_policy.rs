@@ -0,0 +1,73 @@
+//! Retry policy for batch requests.
+
+use std::time::Duration;
+
+/// Policy for resubmitting listings in chunked batch requests such as
+/// [`create_listings_chunked`](crate::BackpackAPI::create_listings_chunked) which failed with a
+/// retryable error. Only errors where
+/// [`ListingErrorKind::is_retryable`](crate::response::listing::ListingErrorKind::is_retryable)
+/// is `true` are resubmitted.
+///
+/// # Examples
+/// ```
+/// use backpacktf_api::{BackpackAPI, BatchRetryPolicy};
+/// use std::time::Duration;
+///
+/// let backpacktf = BackpackAPI::builder()
+///     .batch_retry_policy(BatchRetryPolicy {
+///         max_retries: 5,
+///         initial_backoff: Duration::from_secs(5),
+///         ..Default::default()
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchRetryPolicy {
+    /// The maximum number of times a single listing is resubmitted.
+    pub max_retries: u32,
+    /// The wait before the first resubmission. This doubles for each following resubmission.
+    pub initial_backoff: Duration,
+    /// The maximum wait between resubmissions.
+    pub max_backoff: Duration,
+}
+
+impl Default for BatchRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl BatchRetryPolicy {
+    /// Gets the wait before the given resubmission, starting at 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let multiplier = 2u32.saturating_pow(retry.saturating_sub(1));
+        
+        self.initial_backoff
+            .saturating_mul(multiplier)
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = BatchRetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(60),
+        };
+        
+        assert_eq!(policy.backoff(1), Duration::from_secs(10));
+        assert_eq!(policy.backoff(2), Duration::from_secs(20));
+        assert_eq!(policy.backoff(3), Duration::from_secs(40));
+        assert_eq!(policy.backoff(4), Duration::from_secs(60));
+        assert_eq!(policy.backoff(40), Duration::from_secs(60));
+    }
+}
@@ -1,5 +1,5 @@
 use super::middleware::get_default_client;
-use crate::BackpackAPI;
+use crate::{BackpackAPI, BatchRetryPolicy};
 use std::sync::Arc;
 use reqwest::cookie::Jar;
 use reqwest_middleware::ClientWithMiddleware;
@@ -13,6 +13,9 @@ pub struct BackpackAPIBuilder {
     token: Option<String>,
     client: Option<ClientWithMiddleware>,
     user_agent: &'static str,
+    batch_retry_policy: Option<BatchRetryPolicy>,
+    dry_run: bool,
+    validate_listings: bool,
 }
 
 impl Default for BackpackAPIBuilder {
@@ -29,6 +32,9 @@ impl BackpackAPIBuilder {
             token: None,
             client: None,
             user_agent: USER_AGENT_STRING,
+            batch_retry_policy: None,
+            dry_run: false,
+            validate_listings: false,
         }
     }
     
@@ -56,6 +62,37 @@ impl BackpackAPIBuilder {
         self
     }
     
+    /// Sets the policy for resubmitting listings in chunked batch requests which failed with a 
+    /// retryable error. By default failed listings are not resubmitted.
+    pub fn batch_retry_policy(mut self, batch_retry_policy: BatchRetryPolicy) -> Self {
+        self.batch_retry_policy = Some(batch_retry_policy);
+        self
+    }
+    
+    /// Sets whether the client is in dry-run mode. In dry-run mode, methods which create, update, 
+    /// or delete listings, archived listings, or alerts run their local validation but do not send 
+    /// any requests. Instead the requests which would have been sent are recorded and can be read 
+    /// using [`BackpackAPI::dry_run_requests`](crate::BackpackAPI::dry_run_requests). Every 
+    /// listing, archived listing, or alert is reported as not sent: methods which return a result 
+    /// for each listing return an error with a kind of 
+    /// [`ListingErrorKind::DryRun`](crate::response::listing::ListingErrorKind::DryRun) for each 
+    /// listing, methods which return a count of deleted listings count none, and other methods 
+    /// return [`Error::DryRun`](crate::error::Error::DryRun).
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+    
+    /// Sets whether listings are validated locally before they are sent. When enabled, methods 
+    /// which create or update listings return 
+    /// [`ParameterError::InvalidListing`](crate::error::ParameterError::InvalidListing) without 
+    /// sending a request if any listing has issues. See 
+    /// [`CreateListing::validate`](crate::request::CreateListing::validate).
+    pub fn validate_listings(mut self, validate_listings: bool) -> Self {
+        self.validate_listings = validate_listings;
+        self
+    }
+    
     /// Builds the [`BackpackAPI`] instance.
     pub fn build(self) -> BackpackAPI {
         let cookies = Arc::new(Jar::default());
@@ -70,6 +107,9 @@ impl BackpackAPIBuilder {
             self.key,
             self.token,
             client,
+            self.batch_retry_policy,
+            self.dry_run,
+            self.validate_listings,
         )
     }
 }
\ No newline at end of file
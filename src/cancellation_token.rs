@@ -0,0 +1,169 @@
+//! Cancellation tokens.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Waker};
+
+/// A token for cancelling long-running operations such as
+/// [`get_all_listings_cancellable`](crate::BackpackAPI::get_all_listings_cancellable) or
+/// [`create_listings_chunked_cancellable`](crate::BackpackAPI::create_listings_chunked_cancellable).
+///
+/// Clones of a token share the same state, so cancelling any clone cancels all of them. When
+/// cancelled, operations stop after the request currently in flight completes and return the
+/// results collected so far.
+///
+/// # Examples
+/// ```
+/// use backpacktf_api::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let cloned = token.clone();
+///
+/// cloned.cancel();
+///
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Wakers>,
+}
+
+/// The wakers of the [`Cancelled`] futures waiting on a token, keyed by the future they belong 
+/// to so they can be removed when the future is dropped.
+#[derive(Debug, Default)]
+struct Wakers {
+    next_key: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+impl CancellationToken {
+    /// Creates a new token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Cancels the token, waking any tasks waiting on [`CancellationToken::cancelled`].
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        
+        let wakers = std::mem::take(&mut self.inner.wakers.lock().unwrap().wakers);
+        
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+    
+    /// Whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+    
+    /// Returns a future which resolves once the token is cancelled.
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled {
+            token: self,
+            key: None,
+        }
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled`].
+#[derive(Debug)]
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    /// The key of the registered waker, if the future has been polled.
+    key: Option<u64>,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+    
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        
+        if this.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        
+        {
+            let mut wakers = this.token.inner.wakers.lock().unwrap();
+            let key = *this.key.get_or_insert_with(|| {
+                wakers.next_key += 1;
+                wakers.next_key
+            });
+            
+            match wakers.wakers.get_mut(&key) {
+                Some(waker) if waker.will_wake(cx.waker()) => {},
+                Some(waker) => *waker = cx.waker().clone(),
+                None => {
+                    wakers.wakers.insert(key, cx.waker().clone());
+                },
+            }
+        }
+        
+        // Check again in case the token was cancelled while registering the waker.
+        if this.token.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.token.inner.wakers.lock().unwrap().wakers.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    #[test]
+    fn cancelled_resolves_after_cancel() {
+        let token = CancellationToken::new();
+        let cloned = token.clone();
+        
+        async_std::task::spawn(async move {
+            async_std::task::sleep(Duration::from_millis(10)).await;
+            cloned.cancel();
+        });
+        
+        let result = async_std::task::block_on(async_std::future::timeout(
+            Duration::from_secs(5),
+            token.cancelled(),
+        ));
+        
+        assert!(result.is_ok());
+        assert!(token.is_cancelled());
+    }
+    
+    #[test]
+    fn dropped_futures_remove_their_wakers() {
+        let token = CancellationToken::new();
+        
+        for _ in 0..10 {
+            let result = async_std::task::block_on(async_std::future::timeout(
+                Duration::from_millis(1),
+                token.cancelled(),
+            ));
+            
+            assert!(result.is_err());
+        }
+        
+        assert!(token.inner.wakers.lock().unwrap().wakers.is_empty());
+    }
+}
@@ -0,0 +1,547 @@
+//! Listing-slot-aware creation queue.
+
+use crate::{BackpackAPI, CancellationToken};
+use crate::api::helpers;
+use crate::error::Error;
+use crate::request::CreateListing;
+use crate::response::listing::{Listing, ListingErrorKind};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use reqwest::StatusCode;
+use serde::Serialize;
+
+/// How listings are evicted to free up slots for higher priority listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Delete the listing.
+    Delete,
+    /// Move the listing to the archive.
+    Archive,
+}
+
+/// A priority queue of listings to create which only submits as many listings as there are free
+/// listing slots.
+///
+/// Listings with a higher priority are submitted first and listings with an equal priority are
+/// submitted in the order they were pushed. Listings which are not submitted stay in the queue
+/// for the next call to [`submit`](CreationQueue::submit).
+///
+/// When [`evict`](CreationQueue::evict) is set, existing listings are ranked with the given
+/// function and the lowest ranked listings are evicted to make room for queued listings with a
+/// higher priority.
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::{BackpackAPI, CreationQueue, EvictionPolicy};
+/// use backpacktf_api::request::CreateListing;
+/// use tf2_price::Currencies;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let backpacktf = BackpackAPI::builder()
+///         .token("token".into())
+///         .build();
+///     let mut queue = CreationQueue::new(&backpacktf)
+///         // Sell listings can be evicted for anything more important than 10
+///         .evict(EvictionPolicy::Archive, |listing| listing.item.id.map(|_| 10));
+///
+///     queue.push(CreateListing::Sell {
+///         id: 7764221391,
+///         currencies: Currencies { keys: 1, weapons: 0 },
+///         details: None,
+///         buyout: true,
+///         offers: true,
+///     }, 20);
+///
+///     let report = queue.submit().await;
+///
+///     println!("{} listings created, {} still queued", report.created.len(), queue.len());
+/// }
+/// ```
+pub struct CreationQueue<'a, T> {
+    api: &'a BackpackAPI,
+    pending: BinaryHeap<Entry<T>>,
+    sequence: u64,
+    eviction: Option<(EvictionPolicy, RankFn<'a>)>,
+}
+
+/// Ranks an existing listing for eviction.
+type RankFn<'a> = Box<dyn Fn(&Listing) -> Option<u32> + Send + Sync + 'a>;
+
+impl<'a, T> CreationQueue<'a, T> {
+    /// Creates a new empty queue using the given API.
+    pub fn new(api: &'a BackpackAPI) -> Self {
+        Self {
+            api,
+            pending: BinaryHeap::new(),
+            sequence: 0,
+            eviction: None,
+        }
+    }
+    
+    /// Evicts existing listings to make room for queued listings with a higher priority. `rank`
+    /// returns the priority of an existing listing, or `None` if the listing should never be
+    /// evicted. Only listings with a strictly lower priority than a queued listing are evicted.
+    pub fn evict<F>(
+        mut self,
+        policy: EvictionPolicy,
+        rank: F,
+    ) -> Self
+    where
+        F: Fn(&Listing) -> Option<u32> + Send + Sync + 'a,
+    {
+        self.eviction = Some((policy, Box::new(rank)));
+        self
+    }
+    
+    /// Adds a listing to the queue. Higher priorities are submitted first.
+    pub fn push(
+        &mut self,
+        listing: CreateListing<T>,
+        priority: u32,
+    ) {
+        self.pending.push(Entry {
+            priority,
+            sequence: self.sequence,
+            listing,
+        });
+        self.sequence += 1;
+    }
+    
+    /// The number of listings in the queue.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+    
+    /// Whether the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+    
+    /// Removes all listings from the queue, returning them from highest to lowest priority.
+    pub fn drain(&mut self) -> Vec<(CreateListing<T>, u32)> {
+        let mut entries = std::mem::take(&mut self.pending).into_sorted_vec();
+        
+        entries.reverse();
+        entries
+            .into_iter()
+            .map(|entry| (entry.listing, entry.priority))
+            .collect()
+    }
+}
+
+impl<T> CreationQueue<'_, T>
+where
+    T: Serialize,
+{
+    /// Submits as many queued listings as there are free listing slots, evicting lower priority
+    /// listings first if eviction is enabled. The classifieds limits are fetched before
+    /// submitting and again after evicting listings. Listings which fail because the listing
+    /// limit was reached or with a retryable error, and listings which weren't sent because a
+    /// chunk failed with a transient error, are put back in the queue. Listings which fail
+    /// permanently are reported in [`failed`](CreationReport::failed) and removed from the
+    /// queue.
+    pub async fn submit(&mut self) -> CreationReport<T> {
+        let mut report = CreationReport {
+            created: Vec::new(),
+            dry_run: Vec::new(),
+            failed: Vec::new(),
+            evicted: Vec::new(),
+            requeued: 0,
+            errors: Vec::new(),
+        };
+        
+        if self.pending.is_empty() {
+            return report;
+        }
+        
+        let mut free = match self.free_slots().await {
+            Ok(free) => free,
+            Err(error) => {
+                report.errors.push(error);
+                return report;
+            },
+        };
+        
+        if self.pending.len() > free && self.eviction.is_some() {
+            self.evict_listings(free, &mut report).await;
+            
+            if !report.evicted.is_empty() {
+                match self.free_slots().await {
+                    Ok(refreshed) => free = refreshed,
+                    Err(error) => report.errors.push(error),
+                }
+            }
+        }
+        
+        let count = free.min(self.pending.len());
+        let mut entries = Vec::with_capacity(count);
+        
+        while entries.len() < count {
+            let Some(entry) = self.pending.pop() else {
+                break;
+            };
+            
+            entries.push(entry);
+        }
+        
+        if entries.is_empty() {
+            return report;
+        }
+        
+        let (queries, keys): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .map(|entry| (entry.listing, (entry.priority, entry.sequence)))
+            .unzip();
+        let (results, error) = self.api.create_listings_chunked(&queries).await;
+        // Take ownership of the outcomes so the queries can be moved out afterwards
+        let outcomes = results
+            .into_iter()
+            .map(|result| result.map_err(|error| error.message))
+            .collect::<Vec<_>>();
+        let mut outcomes = outcomes.into_iter();
+        let unsent = match &error {
+            Some(error) if !is_transient(error) => Some(error.to_string()),
+            _ => None,
+        };
+        
+        report.errors.extend(error);
+        
+        for (listing, (priority, sequence)) in queries.into_iter().zip(keys) {
+            let outcome = match outcomes.next() {
+                Some(Ok(created)) => {
+                    report.created.push(created);
+                    continue;
+                },
+                Some(Err(message)) => {
+                    let kind = ListingErrorKind::from(message.as_str());
+                    
+                    if kind == ListingErrorKind::DryRun {
+                        report.dry_run.push(listing);
+                        continue;
+                    }
+                    
+                    if kind == ListingErrorKind::ListingLimitReached || kind.is_retryable() {
+                        None
+                    } else {
+                        Some(message)
+                    }
+                },
+                // The listing was never sent
+                None => unsent.clone(),
+            };
+            
+            if let Some(message) = outcome {
+                report.failed.push((listing, message));
+                continue;
+            }
+            
+            self.pending.push(Entry {
+                priority,
+                sequence,
+                listing,
+            });
+            report.requeued += 1;
+        }
+        
+        report
+    }
+    
+    async fn free_slots(&self) -> Result<usize, Error> {
+        let limits = self.api.classifieds_limits().await?;
+        
+        Ok(limits.total.saturating_sub(limits.used) as usize)
+    }
+    
+    /// Evicts the lowest ranked existing listings for each queued listing which doesn't fit in
+    /// the free slots and has a higher priority.
+    async fn evict_listings(
+        &self,
+        free: usize,
+        report: &mut CreationReport<T>,
+    ) {
+        let Some((policy, rank)) = &self.eviction else {
+            return;
+        };
+        let (listings, error) = self.api.get_all_listings().await;
+        
+        if let Some(error) = error {
+            // Evicting from a partial list could evict listings which outrank ones not fetched
+            report.errors.push(error);
+            return;
+        }
+        
+        let mut candidates = listings
+            .iter()
+            .filter(|listing| !listing.archived)
+            .filter_map(|listing| rank(listing).map(|priority| (priority, listing.id.as_str())))
+            .collect::<Vec<_>>();
+        let mut priorities = self.pending
+            .iter()
+            .map(|entry| entry.priority)
+            .collect::<Vec<_>>();
+        
+        candidates.sort_by_key(|(priority, _id)| *priority);
+        priorities.sort_by(|a, b| b.cmp(a));
+        
+        let evict = priorities
+            .into_iter()
+            .skip(free)
+            .zip(candidates)
+            .take_while(|(priority, (candidate, _id))| candidate < priority)
+            .map(|(_priority, (_candidate, id))| id.to_owned())
+            .collect::<Vec<_>>();
+        
+        if evict.is_empty() {
+            return;
+        }
+        
+        // Each listing is evicted with its own request so that results match their IDs
+        let ids = evict
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let cancel = CancellationToken::new();
+        let rate_limited = !self.api.is_dry_run();
+        let results = match policy {
+            EvictionPolicy::Delete => helpers::submit_each(ids, rate_limited, &cancel, |id| {
+                self.api.delete_listing(id)
+            }).await,
+            EvictionPolicy::Archive => helpers::submit_each(ids, rate_limited, &cancel, |id| {
+                self.api.archive_listing(id)
+            }).await,
+        };
+        
+        for (id, result) in results {
+            match result {
+                Ok(()) | Err(Error::DryRun(_)) => report.evicted.push(id.to_owned()),
+                Err(error) => report.errors.push(error),
+            }
+        }
+    }
+}
+
+/// Whether a request may succeed if it is sent again.
+fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::Http(response) => {
+            let status = response.status();
+            
+            status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        },
+        Error::Reqwest(_) | Error::ReqwestMiddleware(_) => true,
+        _ => false,
+    }
+}
+
+impl<T> fmt::Debug for CreationQueue<'_, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreationQueue")
+            .field("api", &self.api)
+            .field("pending", &self.pending)
+            .field("sequence", &self.sequence)
+            .field("eviction", &self.eviction.as_ref().map(|(policy, _rank)| policy))
+            .finish()
+    }
+}
+
+/// The results of [`CreationQueue::submit`].
+#[derive(Debug)]
+pub struct CreationReport<T> {
+    /// The listings which were created.
+    pub created: Vec<Listing>,
+    /// The listings whose requests were recorded instead of sent because the client is in
+    /// dry-run mode. These are removed from the queue.
+    pub dry_run: Vec<CreateListing<T>>,
+    /// The listings which failed to be created along with the error message.
+    pub failed: Vec<(CreateListing<T>, String)>,
+    /// IDs of listings which were evicted, including listings whose requests were recorded in
+    /// dry-run mode.
+    pub evicted: Vec<String>,
+    /// The number of listings which were put back in the queue.
+    pub requeued: usize,
+    /// Errors which stopped a step before it completed.
+    pub errors: Vec<Error>,
+}
+
+/// A queued listing.
+#[derive(Debug)]
+struct Entry<T> {
+    priority: u32,
+    sequence: u64,
+    listing: CreateListing<T>,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Earlier entries come first among entries with an equal priority
+        self.priority.cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::mock::{mock_api, mock_client, mock_response};
+    use crate::error::ParameterError;
+    use tf2_price::Currencies;
+    
+    const LIMITS: &str = r#"{"listings":{"promotionSlotsAvailable":0,"used":USED,"total":3,"baseline":3,"donationBonus":0,"giftedPremiumMonthsBonus":0,"multiplier":1,"twitterFollowerBonus":0,"acceptedSuggestionBonus":0,"mvpDonationBonus":0,"groupMembershipBonus":0}}"#;
+    
+    fn sell(id: u64) -> CreateListing<Currencies> {
+        CreateListing::Sell {
+            id,
+            currencies: Currencies { keys: 1, weapons: 0 },
+            details: None,
+            buyout: true,
+            offers: true,
+        }
+    }
+    
+    #[test]
+    fn drains_in_priority_order() {
+        let api = BackpackAPI::builder().token("token".into()).build();
+        let mut queue = CreationQueue::new(&api);
+        
+        queue.push(sell(1), 1);
+        queue.push(sell(2), 5);
+        queue.push(sell(3), 1);
+        queue.push(sell(4), 5);
+        
+        let ids = queue.drain()
+            .into_iter()
+            .map(|(listing, _priority)| match listing {
+                CreateListing::Sell { id, .. } => id,
+                CreateListing::Buy { .. } => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        
+        assert_eq!(ids, vec![2, 4, 1, 3]);
+        assert!(queue.is_empty());
+    }
+    
+    #[test]
+    fn requeues_only_retryable_failures() {
+        let api = mock_api(|request| match request.url().path() {
+            "/api/classifieds/limits" => mock_response(200, &LIMITS.replace("USED", "0")),
+            _ => mock_response(200, r#"[
+                {"error":{"message":"Listing limit reached"}},
+                {"error":{"message":"Too many requests"}},
+                {"error":{"message":"Item not in inventory"}}
+            ]"#),
+        });
+        let mut queue = CreationQueue::new(&api);
+        
+        queue.push(sell(1), 3);
+        queue.push(sell(2), 2);
+        queue.push(sell(3), 1);
+        
+        let report = async_std::task::block_on(queue.submit());
+        
+        assert_eq!(report.requeued, 2);
+        assert!(matches!(
+            report.failed.as_slice(),
+            [(CreateListing::Sell { id: 3, .. }, message)] if message == "Item not in inventory",
+        ));
+        assert_eq!(queue.len(), 2);
+    }
+    
+    #[test]
+    fn requeues_unsent_listings_only_after_transient_errors() {
+        for (status, requeued) in [(500, 1), (400, 0)] {
+            let api = mock_api(move |request| match request.url().path() {
+                "/api/classifieds/limits" => mock_response(200, &LIMITS.replace("USED", "0")),
+                _ => mock_response(status, "{}"),
+            });
+            let mut queue = CreationQueue::new(&api);
+            
+            queue.push(sell(1), 1);
+            
+            let report = async_std::task::block_on(queue.submit());
+            
+            assert_eq!(report.requeued, requeued);
+            assert_eq!(report.failed.len(), 1 - requeued);
+            assert_eq!(queue.len(), requeued);
+        }
+    }
+    
+    #[test]
+    fn removes_dry_run_listings_from_the_queue() {
+        let api = BackpackAPI::builder()
+            .token("token".into())
+            .client(mock_client(|_request| mock_response(200, &LIMITS.replace("USED", "0"))))
+            .dry_run(true)
+            .build();
+        let mut queue = CreationQueue::new(&api);
+        
+        queue.push(sell(1), 1);
+        queue.push(sell(2), 1);
+        
+        let report = async_std::task::block_on(queue.submit());
+        
+        assert_eq!(report.dry_run.len(), 2);
+        assert_eq!(report.requeued, 0);
+        assert!(report.failed.is_empty());
+        assert!(queue.is_empty());
+        assert_eq!(api.dry_run_requests().len(), 1);
+    }
+    
+    #[test]
+    fn evicts_deleted_listings_by_id() {
+        let api = mock_api(|request| match request.url().path() {
+            "/api/classifieds/limits" => mock_response(200, &LIMITS.replace("USED", "3")),
+            "/api/v2/classifieds/listings" => mock_response(200, include_str!("api/fixtures/get_listings.json")),
+            "/api/v2/classifieds/listings/440_7764221391" => mock_response(404, "{}"),
+            _ => mock_response(200, "{}"),
+        });
+        let mut queue = CreationQueue::new(&api)
+            .evict(EvictionPolicy::Delete, |listing| match listing.id.as_str() {
+                "440_7764221391" | "440_7188274384" => Some(10),
+                _ => None,
+            });
+        
+        queue.push(sell(1), 20);
+        queue.push(sell(2), 20);
+        
+        let report = async_std::task::block_on(queue.submit());
+        
+        // The first listing failed to be deleted so only the second is evicted
+        assert_eq!(report.evicted, vec!["440_7188274384".to_string()]);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(queue.len(), 2);
+    }
+    
+    #[test]
+    fn keeps_listings_queued_when_limits_are_unavailable() {
+        let api = BackpackAPI::builder().build();
+        let mut queue = CreationQueue::new(&api);
+        
+        queue.push(sell(1), 1);
+        
+        let report = async_std::task::block_on(queue.submit());
+        
+        // The limits can't be fetched without a token
+        assert!(matches!(report.errors.as_slice(), [Error::Parameter(ParameterError::MissingToken)]));
+        assert!(report.created.is_empty());
+        assert_eq!(queue.len(), 1);
+    }
+}
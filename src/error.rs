@@ -22,8 +22,19 @@ pub enum Error {
     /// Unexpected response. Check the message for more details.
     #[error("Unexpected response: {}", .0)]
     Response(String),
+    /// The operation was cancelled using a [`CancellationToken`](crate::CancellationToken).
+    #[error("Operation was cancelled")]
+    Cancelled,
+    /// The client is in dry-run mode and the method has no response to return without sending 
+    /// its request. Contains the request which would have been sent.
+    #[error("{}", DRY_RUN_MESSAGE)]
+    DryRun(Box<crate::request::DryRunRequest>),
 }
 
+/// The message of [`Error::DryRun`], which is also used as the message of listings in batch 
+/// results whose requests were not sent.
+pub(crate) const DRY_RUN_MESSAGE: &str = "Dry run: request not sent";
+
 impl From<reqwest_middleware::Error> for Error {
     fn from(error: reqwest_middleware::Error) -> Self {
         match error {
@@ -60,6 +71,109 @@ pub enum ParameterError {
         /// The maximum length of the parameter.
         max: usize,
     },
+    /// A listing failed local validation. See
+    /// [`BackpackAPIBuilder::validate_listings`](crate::BackpackAPIBuilder::validate_listings).
+    #[error("Listing at index {} is invalid: {}", .index, .issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>().join(", "))]
+    InvalidListing {
+        /// The index of the listing in the request.
+        index: usize,
+        /// The issues found.
+        issues: Vec<crate::request::ValidationIssue>,
+    },
+}
+
+/// Error parsing a [`ListingId`](crate::ListingId) from a string.
+#[derive(thiserror::Error, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ParseListingIdError {
+    /// The listing ID has too few parts.
+    #[error("Listing ID is missing parts")]
+    MissingParts,
+    /// The listing ID has too many parts.
+    #[error("Listing ID has too many parts")]
+    TooManyParts,
+    /// The appid is not a number.
+    #[error("Invalid appid")]
+    InvalidAppid,
+    /// The asset ID of a sell listing ID is not a number.
+    #[error("Invalid asset ID")]
+    InvalidAssetid,
+    /// The SteamID of a buy listing ID is not a number.
+    #[error("Invalid SteamID")]
+    InvalidSteamID,
+    /// The hash of a buy listing ID is not a 32 character hexadecimal string.
+    #[error("Invalid hash")]
+    InvalidHash,
+}
+
+/// Error parsing a [`DetailsTemplate`](crate::request::DetailsTemplate).
+#[derive(thiserror::Error, PartialEq, Eq, Clone, Debug)]
+pub enum ParseTemplateError {
+    /// A placeholder was opened with `{` but never closed.
+    #[error("Unclosed placeholder at position {}", .position)]
+    UnclosedPlaceholder {
+        /// The character position of the opening brace.
+        position: usize,
+    },
+    /// A `}` was found outside of a placeholder. Use `}}` for a literal brace.
+    #[error("Unmatched closing brace at position {}", .position)]
+    UnmatchedBrace {
+        /// The character position of the closing brace.
+        position: usize,
+    },
+    /// A placeholder has no name.
+    #[error("Empty placeholder at position {}", .position)]
+    EmptyPlaceholder {
+        /// The character position of the opening brace.
+        position: usize,
+    },
+    /// A section was opened but never closed.
+    #[error("Section `{}` is never closed", .name)]
+    UnclosedSection {
+        /// The name of the section.
+        name: String,
+    },
+    /// A section was closed which is not the innermost open section.
+    #[error("Unexpected end of section `{}` at position {}", .name, .position)]
+    UnexpectedSectionEnd {
+        /// The name of the section.
+        name: String,
+        /// The character position of the opening brace.
+        position: usize,
+    },
+}
+
+/// Error rendering a [`DetailsTemplate`](crate::request::DetailsTemplate).
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+pub enum RenderTemplateError {
+    /// The listing's currencies could not be read as an object of "keys" and/or "metal" values.
+    #[error("Currencies are not an object of currency values")]
+    InvalidCurrencies,
+    /// The template uses a placeholder which is neither built-in nor given in the context.
+    #[error("Unknown placeholder `{}`", .0)]
+    UnknownPlaceholder(String),
+    /// The rendered details exceed the maximum length.
+    #[error("Details are {} characters long which exceeds the maximum of {}", .length, .max)]
+    DetailsTooLong {
+        /// The number of characters in the details.
+        length: usize,
+        /// The maximum number of characters allowed.
+        max: usize,
+    },
+}
+
+/// Error converting a response listing into a request listing.
+#[derive(Debug, thiserror::Error)]
+pub enum TryFromListingError {
+    /// The item's defindex is negative e.g. a marketplace.tf cross-listing SKU such as "Random 
+    /// Craft Hat".
+    #[error("Item has a negative defindex: {}", .0)]
+    NegativeDefindex(i32),
+    /// The item of a sell listing has no ID.
+    #[error("Sell listing item is missing an ID")]
+    MissingItemId,
+    /// Error converting the listing's currencies.
+    #[error("{}", .0)]
+    Currencies(#[from] TryFromResponseCurrenciesError),
 }
 
 /// Error converting response currencies to currencies.
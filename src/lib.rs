@@ -3,7 +3,15 @@
 #![warn(missing_docs)]
 
 mod listing_intent;
+mod listing_id;
 mod currency_type;
+mod cancellation_token;
+mod batch_retry_policy;
+mod reconciler;
+mod auto_bump;
+mod promotion_manager;
+mod creation_queue;
+mod order_book;
 mod api;
 mod builder;
 
@@ -17,7 +25,15 @@ pub mod request;
 pub use api::BackpackAPI;
 pub use builder::BackpackAPIBuilder;
 pub use listing_intent::ListingIntent;
+pub use listing_id::ListingId;
 pub use currency_type::CurrencyType;
+pub use cancellation_token::{CancellationToken, Cancelled};
+pub use batch_retry_policy::BatchRetryPolicy;
+pub use reconciler::{Reconciler, ReconcilePlan, ReconcileReport};
+pub use auto_bump::{AutoBumper, AutoBumpOptions, AutoBumpEvent, BumpSkipReason};
+pub use promotion_manager::{PromotionManager, PromotionPlan, PromotionReport};
+pub use creation_queue::{CreationQueue, CreationReport, EvictionPolicy};
+pub use order_book::{OrderBook, BookEntry, Quote, TopOfBookChange, DepthLevel};
 
 pub use tf2_price;
 pub use tf2_enum;
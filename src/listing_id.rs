@@ -0,0 +1,312 @@
+//! Listing IDs.
+
+use crate::SteamID;
+use crate::error::ParseListingIdError;
+use crate::request::CreateListing;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use md5::{Digest, Md5};
+
+/// The appid for Team Fortress 2.
+const TF2_APPID: u32 = 440;
+
+/// The ID of a listing. Sell listing IDs are formatted as `<appid>_<assetid>` e.g.
+/// `440_7764221391`. Buy listing IDs are formatted as `<appid>_<steamid>_<hash>` where the hash
+/// is the MD5 digest of the item's full name e.g.
+/// `440_76561198080179568_76c096345919b66f01980381017e31e8`.
+///
+/// Since listing IDs are deterministic, they can be computed locally and used with methods such
+/// as [`update_listing`](crate::BackpackAPI::update_listing) and
+/// [`delete_listing`](crate::BackpackAPI::delete_listing) without first fetching listings.
+///
+/// # Examples
+/// ```
+/// use backpacktf_api::{ListingId, SteamID};
+///
+/// let steamid = SteamID::from(76561198080179568);
+/// let listing_id = ListingId::buy(steamid, "Lucky Cat Hat");
+///
+/// assert_eq!(listing_id.to_string(), "440_76561198080179568_76c096345919b66f01980381017e31e8");
+/// assert_eq!("440_7764221391".parse::<ListingId>().unwrap(), ListingId::sell(7764221391));
+/// ```
+#[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum ListingId {
+    /// The ID of a sell listing.
+    Sell {
+        /// The appid of the item.
+        appid: u32,
+        /// The asset ID of the item.
+        assetid: u64,
+    },
+    /// The ID of a buy listing.
+    Buy {
+        /// The appid of the item.
+        appid: u32,
+        /// The SteamID of the listing's owner.
+        steamid: SteamID,
+        /// The MD5 digest of the item's full name.
+        hash: [u8; 16],
+    },
+}
+
+impl ListingId {
+    /// Creates the ID of a Team Fortress 2 sell listing for the item with the given asset ID.
+    pub fn sell(assetid: u64) -> Self {
+        Self::Sell {
+            appid: TF2_APPID,
+            assetid,
+        }
+    }
+    
+    /// Creates the ID of a Team Fortress 2 buy listing from the owner's SteamID and the item's
+    /// full name as it appears on backpack.tf, including any prefixes e.g.
+    /// "Strange Professional Killstreak Pain Train". This is the same as
+    /// [`Item::name`](crate::response::listing::Item::name) on the listing's item.
+    pub fn buy(
+        steamid: SteamID,
+        item_name: &str,
+    ) -> Self {
+        Self::Buy {
+            appid: TF2_APPID,
+            steamid,
+            hash: Md5::digest(item_name.as_bytes()).into(),
+        }
+    }
+    
+    /// The appid of the listing's item.
+    pub fn appid(&self) -> u32 {
+        match self {
+            Self::Sell { appid, .. } => *appid,
+            Self::Buy { appid, .. } => *appid,
+        }
+    }
+}
+
+impl<T> CreateListing<T> {
+    /// Computes the ID the listing will have once created. Buy listing IDs depend on the item's
+    /// full name, which is built from the item's attributes using
+    /// [`BuyListingItem::full_name`](crate::request::BuyListingItem::full_name). The item's name
+    /// and unusual effect aren't attributes of the item, so `base_name` and `effect_name` are
+    /// given in the same form as for `full_name`. Both are ignored for sell listings.
+    ///
+    /// # Examples
+    /// ```
+    /// use backpacktf_api::{ListingId, SteamID};
+    /// use backpacktf_api::request::{BuyListingItem, CreateListing};
+    /// use tf2_price::Currencies;
+    /// use tf2_enum::Quality;
+    ///
+    /// let listing = CreateListing::Buy {
+    ///     item: BuyListingItem::new(30998, Quality::Unique),
+    ///     currencies: Currencies { keys: 2, weapons: 0 },
+    ///     details: None,
+    ///     buyout: true,
+    ///     offers: true,
+    /// };
+    /// let steamid = SteamID::from(76561198080179568);
+    /// let listing_id = listing.listing_id(steamid, "Lucky Cat Hat", None);
+    ///
+    /// assert_eq!(listing_id.to_string(), "440_76561198080179568_76c096345919b66f01980381017e31e8");
+    /// ```
+    pub fn listing_id(
+        &self,
+        steamid: SteamID,
+        base_name: &str,
+        effect_name: Option<&str>,
+    ) -> ListingId {
+        match self {
+            CreateListing::Sell { id, .. } => ListingId::sell(*id),
+            CreateListing::Buy { item, .. } => ListingId::buy(
+                steamid,
+                &item.full_name(base_name, effect_name),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ListingId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sell { appid, assetid } => write!(f, "{appid}_{assetid}"),
+            Self::Buy { appid, steamid, hash } => {
+                write!(f, "{appid}_{}_", u64::from(*steamid))?;
+                
+                for byte in hash {
+                    write!(f, "{byte:02x}")?;
+                }
+                
+                Ok(())
+            },
+        }
+    }
+}
+
+impl FromStr for ListingId {
+    type Err = ParseListingIdError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('_');
+        let appid = parts
+            .next()
+            .and_then(|appid| appid.parse::<u32>().ok())
+            .ok_or(ParseListingIdError::InvalidAppid)?;
+        let second = parts.next().ok_or(ParseListingIdError::MissingParts)?;
+        
+        let Some(hash) = parts.next() else {
+            let assetid = second.parse::<u64>()
+                .map_err(|_| ParseListingIdError::InvalidAssetid)?;
+            
+            return Ok(Self::Sell {
+                appid,
+                assetid,
+            });
+        };
+        
+        if parts.next().is_some() {
+            return Err(ParseListingIdError::TooManyParts);
+        }
+        
+        let steamid = second.parse::<u64>()
+            .map_err(|_| ParseListingIdError::InvalidSteamID)?;
+        
+        Ok(Self::Buy {
+            appid,
+            steamid: SteamID::from(steamid),
+            hash: parse_hash(hash).ok_or(ParseListingIdError::InvalidHash)?,
+        })
+    }
+}
+
+impl From<ListingId> for String {
+    fn from(listing_id: ListingId) -> Self {
+        listing_id.to_string()
+    }
+}
+
+impl Serialize for ListingId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ListingId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let s = String::deserialize(deserializer)?;
+        
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parses a 32 character lowercase or uppercase hexadecimal string.
+fn parse_hash(s: &str) -> Option<[u8; 16]> {
+    if s.len() != 32 || !s.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    
+    let mut hash = [0u8; 16];
+    
+    for (byte, chunk) in hash.iter_mut().zip(s.as_bytes().chunks_exact(2)) {
+        // Input is ASCII hex digits so this is always valid UTF-8
+        let digits = std::str::from_utf8(chunk).ok()?;
+        
+        *byte = u8::from_str_radix(digits, 16).ok()?;
+    }
+    
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::BuyListingItem;
+    use crate::response::listing::Listing;
+    use tf2_enum::{KillstreakTier, Quality, Wear};
+    use tf2_price::Currencies;
+    
+    #[test]
+    fn computes_buy_listing_ids_from_fixtures() {
+        let fixtures = [
+            include_str!("response/listing/fixtures/listing.json"),
+            include_str!("response/listing/fixtures/Strange Massed Flies Crone's Dome.json"),
+            include_str!("response/listing/fixtures/missing_paint.json"),
+        ];
+        
+        for fixture in fixtures {
+            let listing: Listing = serde_json::from_str(fixture).unwrap();
+            let listing_id = ListingId::buy(listing.steamid, &listing.item.name);
+            
+            assert_eq!(listing_id.to_string(), listing.id);
+            assert_eq!(listing.id.parse::<ListingId>().unwrap(), listing_id);
+        }
+    }
+    
+    #[test]
+    fn computes_buy_listing_ids_from_item_attributes() {
+        let fixtures = [
+            (include_str!("response/listing/fixtures/listing.json"), "Lucky Cat Hat"),
+            (include_str!("response/listing/fixtures/Strange Massed Flies Crone's Dome.json"), "Crone's Dome"),
+            (include_str!("response/listing/fixtures/missing_paint.json"), "The Wilson Weave"),
+            (include_str!("response/listing/fixtures/not_enough_currency.json"), "Rocket Launcher"),
+        ];
+        
+        for (fixture, base_name) in fixtures {
+            let listing: Listing = serde_json::from_str(fixture).unwrap();
+            let effect_name = listing.item.particle
+                .as_ref()
+                .map(|particle| particle.name.as_str());
+            let create_listing = CreateListing::Buy {
+                item: BuyListingItem::try_from(&listing.item).unwrap(),
+                currencies: Currencies::default(),
+                details: None,
+                buyout: true,
+                offers: true,
+            };
+            let listing_id = create_listing.listing_id(listing.steamid, base_name, effect_name);
+            
+            assert_eq!(listing_id.to_string(), listing.id);
+        }
+    }
+    
+    #[test]
+    fn builds_full_names_from_item_attributes() {
+        let item = BuyListingItem::new(1, Quality::Strange)
+            .craftable(false)
+            .killstreak_tier(KillstreakTier::Professional);
+        
+        assert_eq!(item.full_name("Pain Train", None), "Non-Craftable Strange Professional Killstreak Pain Train");
+        
+        let item = BuyListingItem::new(1, Quality::DecoratedWeapon)
+            .festivized(true)
+            .wear(Some(Wear::FieldTested));
+        
+        assert_eq!(item.full_name("Night Owl Sniper Rifle", None), "Festivized Night Owl Sniper Rifle (Field-Tested)");
+        
+        let item = BuyListingItem::new(1, Quality::Vintage);
+        
+        assert_eq!(item.full_name("The Wilson Weave", Some("Burning Flames")), "Vintage Wilson Weave");
+    }
+    
+    #[test]
+    fn parses_sell_listing_id() {
+        let listing_id: ListingId = serde_json::from_str("\"440_7764221391\"").unwrap();
+        
+        assert_eq!(listing_id, ListingId::sell(7764221391));
+        assert_eq!(serde_json::to_string(&listing_id).unwrap(), "\"440_7764221391\"");
+    }
+    
+    #[test]
+    fn rejects_invalid_listing_ids() {
+        assert_eq!("440".parse::<ListingId>(), Err(ParseListingIdError::MissingParts));
+        assert_eq!("tf_1".parse::<ListingId>(), Err(ParseListingIdError::InvalidAppid));
+        assert_eq!("440_abc".parse::<ListingId>(), Err(ParseListingIdError::InvalidAssetid));
+        assert_eq!("440_76561198080179568_zz".parse::<ListingId>(), Err(ParseListingIdError::InvalidHash));
+        assert_eq!("440_1_2_3".parse::<ListingId>(), Err(ParseListingIdError::TooManyParts));
+    }
+}
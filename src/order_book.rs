@@ -0,0 +1,603 @@
+//! In-memory order book.
+
+use crate::{BackpackAPI, ListingId, ListingIntent, SteamID};
+use crate::error::Error;
+use crate::response::currencies::ResponseCurrencies;
+use crate::response::listing::Listing;
+use crate::response::snapshot::{self, Snapshot};
+use crate::time::ServerTime;
+use std::collections::{BTreeSet, HashMap};
+use async_std::channel::{self, Receiver, Sender};
+use tf2_price::{Currency, FloatCurrencies};
+
+/// The default capacity of subscriber channels.
+const DEFAULT_CHANNEL_CAPACITY: usize = 100;
+
+/// A listing in an [`OrderBook`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookEntry {
+    /// The ID of the listing.
+    pub id: String,
+    /// The SteamID of the listing's user.
+    pub steamid: SteamID,
+    /// The currencies of the listing.
+    pub currencies: ResponseCurrencies,
+    /// The time the listing was bumped at.
+    pub bumped_at: ServerTime,
+    /// Whether the listing is managed by an agent.
+    pub is_automatic: bool,
+}
+
+impl From<&Listing> for BookEntry {
+    fn from(listing: &Listing) -> Self {
+        Self {
+            id: listing.id.clone(),
+            steamid: listing.steamid,
+            currencies: listing.currencies,
+            bumped_at: listing.bumped_at,
+            is_automatic: listing.is_automatic(),
+        }
+    }
+}
+
+/// The best buy and sell prices for an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quote {
+    /// The highest buy price.
+    pub bid: Option<ResponseCurrencies>,
+    /// The lowest sell price.
+    pub ask: Option<ResponseCurrencies>,
+}
+
+/// The top of the book moved for an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopOfBookChange {
+    /// The item's full name.
+    pub item_name: String,
+    /// The quote before the change.
+    pub previous: Quote,
+    /// The quote after the change.
+    pub current: Quote,
+}
+
+/// A price level in the depth of an item's book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthLevel {
+    /// The price of the level.
+    pub currencies: ResponseCurrencies,
+    /// The number of listings at the price.
+    pub count: usize,
+}
+
+/// The listings on one side of an item's book.
+#[derive(Debug, Default)]
+struct Side {
+    entries: HashMap<String, BookEntry>,
+    /// The IDs of listings in in-game currencies ordered by price from lowest to highest.
+    ordered: BTreeSet<(FloatCurrencies, String)>,
+}
+
+impl Side {
+    fn insert(&mut self, entry: BookEntry) {
+        self.remove(&entry.id);
+        
+        if let ResponseCurrencies::InGame(currencies) = entry.currencies {
+            self.ordered.insert((currencies, entry.id.clone()));
+        }
+        
+        self.entries.insert(entry.id.clone(), entry);
+    }
+    
+    fn remove(&mut self, id: &str) -> bool {
+        let Some(entry) = self.entries.remove(id) else {
+            return false;
+        };
+        
+        if let ResponseCurrencies::InGame(currencies) = entry.currencies {
+            self.ordered.remove(&(currencies, entry.id));
+        }
+        
+        true
+    }
+    
+    /// Gets the entries in in-game currencies from lowest to highest price.
+    fn iter(&self) -> impl DoubleEndedIterator<Item = &BookEntry> {
+        self.ordered
+            .iter()
+            .filter_map(|(_currencies, id)| self.entries.get(id))
+    }
+    
+    /// Gets the entries which aren't in in-game currencies.
+    fn unordered(&self) -> impl Iterator<Item = &BookEntry> {
+        self.entries
+            .values()
+            .filter(|entry| !matches!(entry.currencies, ResponseCurrencies::InGame(_)))
+    }
+}
+
+/// The buy and sell listings for an item.
+#[derive(Debug, Default)]
+struct ItemBook {
+    bids: Side,
+    asks: Side,
+}
+
+impl ItemBook {
+    fn side_mut(&mut self, intent: ListingIntent) -> &mut Side {
+        match intent {
+            ListingIntent::Buy => &mut self.bids,
+            ListingIntent::Sell => &mut self.asks,
+        }
+    }
+    
+    fn quote(&self) -> Quote {
+        Quote {
+            bid: self.bids.ordered.last().map(|(currencies, _id)| ResponseCurrencies::InGame(*currencies)),
+            ask: self.asks.ordered.first().map(|(currencies, _id)| ResponseCurrencies::InGame(*currencies)),
+        }
+    }
+}
+
+/// A live in-memory order book of buy and sell listings per item, kept up to date from websocket
+/// events and seeded from classifieds snapshots.
+///
+/// Items are keyed by their full name e.g. "Strange Professional Killstreak Pain Train", which is
+/// the same as [`Item::name`](crate::response::listing::Item::name) and the SKU used by
+/// [`get_snapshot`](BackpackAPI::get_snapshot). Listings in in-game currencies are ordered by
+/// keys then metal. Listings with a hat value or a cash price can't be ordered against them, so
+/// they are kept out of quotes, depth and the ordered listings, and are available from
+/// [`unordered`](OrderBook::unordered).
+///
+/// Methods which change the book return a [`TopOfBookChange`] when the best bid or ask of the
+/// item changed. The same changes are sent to receivers from
+/// [`subscribe`](OrderBook::subscribe). Receivers are bounded by
+/// [`channel_capacity`](OrderBook::channel_capacity) and the oldest changes are dropped when a
+/// receiver falls behind.
+///
+/// With the `websocket` feature, [`apply`](OrderBook::apply) keeps the book up to date from
+/// websocket messages.
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::{BackpackAPI, OrderBook};
+/// use tf2_price::ref_to_weps;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let backpacktf = BackpackAPI::builder()
+///         .token("token".into())
+///         .build();
+///     let mut book = OrderBook::new();
+///     let changes = book.subscribe();
+///
+///     book.seed(&backpacktf, "Strange Pain Train").await?;
+///
+///     let spread = book.spread("Strange Pain Train", ref_to_weps!(60));
+///
+///     println!("{:?} (spread {spread:?})", book.quote("Strange Pain Train"));
+///
+///     while let Ok(change) = changes.recv().await {
+///         println!("{}: {:?}", change.item_name, change.current);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct OrderBook {
+    books: HashMap<String, ItemBook>,
+    subscribers: Vec<Subscriber>,
+    channel_capacity: usize,
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self {
+            books: HashMap::new(),
+            subscribers: Vec::new(),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+        }
+    }
+}
+
+impl OrderBook {
+    /// Creates a new empty order book.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Sets the capacity of channels returned by [`subscribe`](OrderBook::subscribe). When a
+    /// receiver is full the oldest change is dropped to make room for the newest. Defaults to
+    /// 100.
+    pub fn channel_capacity(mut self, channel_capacity: usize) -> Self {
+        self.channel_capacity = channel_capacity.max(1);
+        self
+    }
+    
+    /// Returns a receiver for changes to the top of the book. Receivers which are dropped are
+    /// removed on the next change.
+    pub fn subscribe(&mut self) -> Receiver<TopOfBookChange> {
+        let (sender, receiver) = channel::bounded(self.channel_capacity);
+        
+        self.subscribers.push(Subscriber {
+            sender,
+            receiver: receiver.clone(),
+        });
+        receiver
+    }
+    
+    /// Fetches the snapshot for the item and replaces the item's listings with the listings in
+    /// the snapshot. Events received while the snapshot is fetched which are older than the
+    /// snapshot may be overwritten.
+    pub async fn seed(
+        &mut self,
+        api: &BackpackAPI,
+        item_name: &str,
+    ) -> Result<Option<TopOfBookChange>, Error> {
+        let snapshot = api.get_snapshot(item_name).await?;
+        
+        Ok(self.seed_snapshot(&snapshot))
+    }
+    
+    /// Replaces the listings for the snapshot's item with the listings in the snapshot. Sell
+    /// listings without an item ID are skipped since their listing ID can't be determined.
+    pub fn seed_snapshot(&mut self, snapshot: &Snapshot) -> Option<TopOfBookChange> {
+        let mut book = ItemBook::default();
+        
+        for listing in &snapshot.listings {
+            if let Some(entry) = snapshot_entry(listing, &snapshot.sku) {
+                book.side_mut(listing.intent).insert(entry);
+            }
+        }
+        
+        let previous = self.quote(&snapshot.sku);
+        
+        self.books.insert(snapshot.sku.clone(), book);
+        self.notify(&snapshot.sku, previous)
+    }
+    
+    /// Adds or replaces a listing. Archived listings are removed since they are not active.
+    pub fn update(&mut self, listing: &Listing) -> Option<TopOfBookChange> {
+        if listing.archived {
+            return self.remove(listing);
+        }
+        
+        let item_name = &listing.item.name;
+        let previous = self.quote(item_name);
+        
+        self.books
+            .entry(item_name.clone())
+            .or_default()
+            .side_mut(listing.intent)
+            .insert(BookEntry::from(listing));
+        self.notify(item_name, previous)
+    }
+    
+    /// Removes a listing.
+    pub fn remove(&mut self, listing: &Listing) -> Option<TopOfBookChange> {
+        let item_name = &listing.item.name;
+        let previous = self.quote(item_name);
+        let book = self.books.get_mut(item_name)?;
+        
+        if !book.side_mut(listing.intent).remove(&listing.id) {
+            return None;
+        }
+        
+        if book.bids.entries.is_empty() && book.asks.entries.is_empty() {
+            self.books.remove(item_name);
+        }
+        
+        self.notify(item_name, previous)
+    }
+    
+    /// Applies a listing update or delete from the websocket. Other messages are ignored.
+    #[cfg(feature = "websocket")]
+    pub fn apply(&mut self, message: &crate::websocket::Message) -> Option<TopOfBookChange> {
+        use crate::websocket::Message;
+        
+        match message {
+            Message::ListingUpdate(listing) => self.update(listing),
+            Message::ListingDelete(listing) => self.remove(listing),
+            _ => None,
+        }
+    }
+    
+    /// Removes all listings for an item.
+    pub fn clear_item(&mut self, item_name: &str) -> Option<TopOfBookChange> {
+        let previous = self.quote(item_name);
+        
+        self.books.remove(item_name)?;
+        self.notify(item_name, previous)
+    }
+    
+    /// The names of the items in the book.
+    pub fn items(&self) -> impl Iterator<Item = &str> {
+        self.books.keys().map(String::as_str)
+    }
+    
+    /// The best bid and ask for an item.
+    pub fn quote(&self, item_name: &str) -> Quote {
+        self.books
+            .get(item_name)
+            .map(ItemBook::quote)
+            .unwrap_or_default()
+    }
+    
+    /// The buy listing with the highest price for an item.
+    pub fn best_bid(&self, item_name: &str) -> Option<&BookEntry> {
+        self.bids(item_name).next()
+    }
+    
+    /// The sell listing with the lowest price for an item.
+    pub fn best_ask(&self, item_name: &str) -> Option<&BookEntry> {
+        self.asks(item_name).next()
+    }
+    
+    /// The buy listings in in-game currencies for an item from highest to lowest price.
+    pub fn bids(&self, item_name: &str) -> impl Iterator<Item = &BookEntry> {
+        self.books
+            .get(item_name)
+            .into_iter()
+            .flat_map(|book| book.bids.iter().rev())
+    }
+    
+    /// The sell listings in in-game currencies for an item from lowest to highest price.
+    pub fn asks(&self, item_name: &str) -> impl Iterator<Item = &BookEntry> {
+        self.books
+            .get(item_name)
+            .into_iter()
+            .flat_map(|book| book.asks.iter())
+    }
+    
+    /// The listings for one side of an item's book whose currencies include a hat value or are
+    /// cash, in no particular order.
+    pub fn unordered(
+        &self,
+        item_name: &str,
+        intent: ListingIntent,
+    ) -> impl Iterator<Item = &BookEntry> {
+        self.books
+            .get(item_name)
+            .into_iter()
+            .flat_map(move |book| match intent {
+                ListingIntent::Buy => book.bids.unordered(),
+                ListingIntent::Sell => book.asks.unordered(),
+            })
+    }
+    
+    /// The difference between the best ask and best bid for an item in weapons using the given
+    /// key price. `None` if either side has no listings in in-game currencies.
+    pub fn spread(
+        &self,
+        item_name: &str,
+        key_price_weapons: Currency,
+    ) -> Option<Currency> {
+        let Quote { bid, ask } = self.quote(item_name);
+        let (ResponseCurrencies::InGame(bid), ResponseCurrencies::InGame(ask)) = (bid?, ask?) else {
+            return None;
+        };
+        
+        Some(ask.to_weapons(key_price_weapons) - bid.to_weapons(key_price_weapons))
+    }
+    
+    /// The number of listings at each of the best `levels` prices for one side of an item's
+    /// book, starting from the best price.
+    pub fn depth(
+        &self,
+        item_name: &str,
+        intent: ListingIntent,
+        levels: usize,
+    ) -> Vec<DepthLevel> {
+        let entries: Box<dyn Iterator<Item = &BookEntry>> = match intent {
+            ListingIntent::Buy => Box::new(self.bids(item_name)),
+            ListingIntent::Sell => Box::new(self.asks(item_name)),
+        };
+        let mut depth: Vec<DepthLevel> = Vec::new();
+        
+        for entry in entries {
+            if let Some(level) = depth.last_mut().filter(|level| level.currencies == entry.currencies) {
+                level.count += 1;
+                continue;
+            }
+            
+            if depth.len() == levels {
+                break;
+            }
+            
+            depth.push(DepthLevel {
+                currencies: entry.currencies,
+                count: 1,
+            });
+        }
+        
+        depth
+    }
+    
+    /// Notifies subscribers if the quote for the item changed.
+    fn notify(
+        &mut self,
+        item_name: &str,
+        previous: Quote,
+    ) -> Option<TopOfBookChange> {
+        let current = self.quote(item_name);
+        
+        if current == previous {
+            return None;
+        }
+        
+        let change = TopOfBookChange {
+            item_name: item_name.to_owned(),
+            previous,
+            current,
+        };
+        
+        self.subscribers.retain(|subscriber| subscriber.send(change.clone()));
+        Some(change)
+    }
+}
+
+/// A subscriber to changes to the top of the book.
+#[derive(Debug)]
+struct Subscriber {
+    sender: Sender<TopOfBookChange>,
+    /// A receiver for the same channel used to drop the oldest change when the channel is full.
+    receiver: Receiver<TopOfBookChange>,
+}
+
+impl Subscriber {
+    /// Sends a change, dropping the oldest change if the channel is full. Returns `false` if the
+    /// subscriber's receiver was dropped.
+    fn send(&self, change: TopOfBookChange) -> bool {
+        // Only the subscriber's own receiver remains once the returned receiver is dropped
+        if self.sender.receiver_count() <= 1 {
+            return false;
+        }
+        
+        if self.sender.is_full() {
+            let _ = self.receiver.try_recv();
+        }
+        
+        self.sender.try_send(change).is_ok()
+    }
+}
+
+/// Converts a snapshot listing into an entry. The listing ID is computed from the asset ID for
+/// sell listings and the item name for buy listings.
+fn snapshot_entry(
+    listing: &snapshot::Listing,
+    item_name: &str,
+) -> Option<BookEntry> {
+    let id = match listing.intent {
+        ListingIntent::Buy => ListingId::buy(listing.steamid, item_name),
+        ListingIntent::Sell => ListingId::sell(listing.item.id?),
+    };
+    
+    Some(BookEntry {
+        id: id.to_string(),
+        steamid: listing.steamid,
+        currencies: listing.currencies,
+        bumped_at: listing.bump,
+        is_automatic: listing.is_automatic(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::listing::tests::get_listing;
+    use tf2_price::FloatCurrencies;
+    
+    fn in_game(keys: f32, metal: f32) -> ResponseCurrencies {
+        ResponseCurrencies::InGame(FloatCurrencies { keys, metal })
+    }
+    
+    fn book_listing(
+        id: &str,
+        intent: ListingIntent,
+        currencies: ResponseCurrencies,
+    ) -> Listing {
+        let mut listing = get_listing(id);
+        
+        listing.intent = intent;
+        listing.currencies = currencies;
+        listing
+    }
+    
+    #[test]
+    fn seeds_from_snapshot() {
+        let snapshot: Snapshot = serde_json::from_str(include_str!("response/snapshot/fixtures/snapshot.json")).unwrap();
+        let mut book = OrderBook::new();
+        let item_name = "Purple Energy Trophy Belt";
+        let change = book.seed_snapshot(&snapshot).unwrap();
+        
+        assert_eq!(change.current, Quote {
+            bid: Some(in_game(118.0, 17.72)),
+            ask: Some(in_game(180.0, 0.0)),
+        });
+        assert_eq!(book.best_ask(item_name).unwrap().id, "440_10080129222");
+        assert_eq!(book.bids(item_name).count(), 14);
+        assert_eq!(book.depth(item_name, ListingIntent::Buy, 3), vec![
+            DepthLevel { currencies: in_game(118.0, 17.72), count: 1 },
+            DepthLevel { currencies: in_game(118.0, 0.88), count: 1 },
+            DepthLevel { currencies: in_game(117.0, 60.0), count: 1 },
+        ]);
+        assert_eq!(book.depth(item_name, ListingIntent::Buy, 4)[3].count, 9);
+        // 62 keys at 60 refined less 17.72 refined, which is 319 weapons
+        assert_eq!(book.spread(item_name, 60 * 18), Some(62 * 60 * 18 - 319));
+    }
+    
+    #[test]
+    fn notifies_when_top_of_book_moves() {
+        let mut book = OrderBook::new();
+        let subscriber = book.subscribe();
+        let ask = book_listing("440_1", ListingIntent::Sell, in_game(20.0, 0.0));
+        let item_name = ask.item.name.clone();
+        
+        assert!(book.update(&ask).is_some());
+        // A worse price doesn't move the top of the book
+        assert!(book.update(&book_listing("440_2", ListingIntent::Sell, in_game(21.0, 0.0))).is_none());
+        assert!(book.update(&book_listing("440_3", ListingIntent::Buy, in_game(18.0, 5.0))).is_some());
+        assert_eq!(book.spread(&item_name, 60 * 18), Some(2 * 60 * 18 - 5 * 18));
+        
+        let change = book.remove(&ask).unwrap();
+        
+        assert_eq!(change.previous.ask, Some(in_game(20.0, 0.0)));
+        assert_eq!(change.current.ask, Some(in_game(21.0, 0.0)));
+        assert_eq!(subscriber.len(), 3);
+        assert!(book.remove(&ask).is_none());
+    }
+    
+    #[test]
+    fn keeps_other_currencies_out_of_quotes() {
+        let mut book = OrderBook::new();
+        let listings = [
+            book_listing("440_1", ListingIntent::Buy, in_game(10.0, 0.0)),
+            book_listing("440_2", ListingIntent::Buy, ResponseCurrencies::Cash(100.0)),
+            book_listing("440_3", ListingIntent::Buy, ResponseCurrencies::InGameWithHat { keys: 1.0, metal: 0.0, hat: 1.0 }),
+            book_listing("440_4", ListingIntent::Sell, ResponseCurrencies::Cash(1.0)),
+            book_listing("440_5", ListingIntent::Sell, in_game(20.0, 0.0)),
+        ];
+        let item_name = listings[0].item.name.clone();
+        
+        for listing in &listings {
+            book.update(listing);
+        }
+        
+        assert_eq!(book.quote(&item_name), Quote {
+            bid: Some(in_game(10.0, 0.0)),
+            ask: Some(in_game(20.0, 0.0)),
+        });
+        assert_eq!(book.best_bid(&item_name).unwrap().id, "440_1");
+        assert_eq!(book.best_ask(&item_name).unwrap().id, "440_5");
+        assert_eq!(book.depth(&item_name, ListingIntent::Buy, 10).len(), 1);
+        
+        let mut unordered = book.unordered(&item_name, ListingIntent::Buy)
+            .map(|entry| entry.id.as_str())
+            .collect::<Vec<_>>();
+        
+        unordered.sort();
+        assert_eq!(unordered, vec!["440_2", "440_3"]);
+        assert_eq!(book.unordered(&item_name, ListingIntent::Sell).count(), 1);
+        // Removing a listing with other currencies doesn't move the top of the book
+        assert!(book.remove(&listings[1]).is_none());
+        assert_eq!(book.unordered(&item_name, ListingIntent::Buy).count(), 1);
+    }
+    
+    #[test]
+    fn drops_oldest_changes_for_slow_subscribers() {
+        let mut book = OrderBook::new().channel_capacity(2);
+        let subscriber = book.subscribe();
+        let dropped = book.subscribe();
+        
+        drop(dropped);
+        
+        for (index, keys) in [20.0, 19.0, 18.0].into_iter().enumerate() {
+            assert!(book.update(&book_listing(&format!("440_{index}"), ListingIntent::Sell, in_game(keys, 0.0))).is_some());
+        }
+        
+        assert_eq!(book.subscribers.len(), 1);
+        assert_eq!(subscriber.len(), 2);
+        assert_eq!(subscriber.try_recv().unwrap().current.ask, Some(in_game(19.0, 0.0)));
+        assert_eq!(subscriber.try_recv().unwrap().current.ask, Some(in_game(18.0, 0.0)));
+    }
+}
@@ -0,0 +1,350 @@
+//! Listing promotion management.
+
+use crate::BackpackAPI;
+use crate::error::Error;
+use crate::response::classifieds_limits::ClassifiedsLimits;
+use crate::response::listing::Listing;
+use std::cmp::Ordering;
+
+/// Keeps the highest ranked listings promoted.
+///
+/// Listings are ranked using a function which returns a sortable key for each listing, or `None`
+/// if the listing should never be promoted. Listings with the highest keys are promoted first.
+/// The number of promoted listings is limited by the promotion slots reported by
+/// [`classifieds_limits`](BackpackAPI::classifieds_limits) and optionally by
+/// [`max_promoted`](PromotionManager::max_promoted).
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::{BackpackAPI, PromotionManager};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let backpacktf = BackpackAPI::builder()
+///         .token("token".into())
+///         .build();
+///     // Promote the most valuable listings
+///     let manager = PromotionManager::new(&backpacktf, |listing| {
+///         listing.value.as_ref().map(|value| value.raw)
+///     })
+///         .max_promoted(10);
+///     let plan = manager.plan().await?;
+///     let report = manager.apply(&plan).await;
+///
+///     println!("{} listings promoted", report.promoted.len());
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct PromotionManager<'a, F> {
+    api: &'a BackpackAPI,
+    rank: F,
+    max_promoted: Option<u32>,
+}
+
+impl<'a, F, K> PromotionManager<'a, F>
+where
+    F: Fn(&Listing) -> Option<K>,
+    K: PartialOrd,
+{
+    /// Creates a new promotion manager using the given API and ranking function.
+    pub fn new(
+        api: &'a BackpackAPI,
+        rank: F,
+    ) -> Self {
+        Self {
+            api,
+            rank,
+            max_promoted: None,
+        }
+    }
+    
+    /// The maximum number of listings to keep promoted. By default all available promotion
+    /// slots are used.
+    pub fn max_promoted(mut self, max_promoted: u32) -> Self {
+        self.max_promoted = Some(max_promoted);
+        self
+    }
+    
+    /// Fetches the current listings and classifieds limits and computes the promotions and
+    /// demotions needed. If the listings can't be fully fetched an error is returned, since a
+    /// partial state would cause promoted listings to be demoted.
+    pub async fn plan(&self) -> Result<PromotionPlan, Error> {
+        let (listings, error) = self.api.get_all_listings().await;
+        
+        if let Some(error) = error {
+            return Err(error);
+        }
+        
+        let limits = self.api.classifieds_limits().await?;
+        
+        Ok(PromotionPlan::new(&listings, &limits, self.max_promoted, &self.rank))
+    }
+    
+    /// Applies a plan. Demotions are applied first to free up promotion slots, followed by
+    /// promotions. The classifieds limits are re-checked after each change and promotions stop
+    /// once no promotion slots are available. Errors for a listing do not stop the following
+    /// listings. When the client is in dry-run mode, listings whose requests were recorded are
+    /// reported as promoted or demoted.
+    pub async fn apply(
+        &self,
+        plan: &PromotionPlan,
+    ) -> PromotionReport {
+        let mut report = PromotionReport {
+            promoted: Vec::new(),
+            demoted: Vec::new(),
+            skipped: Vec::new(),
+            failed: Vec::new(),
+            errors: Vec::new(),
+            limits: None,
+        };
+        
+        for id in &plan.demote {
+            match self.api.demote_listing(id).await {
+                Ok(()) | Err(Error::DryRun(_)) => report.demoted.push(id.clone()),
+                Err(error) => report.failed.push((id.clone(), error)),
+            }
+        
+            self.refresh_limits(&mut report).await;
+        }
+        
+        for (index, id) in plan.promote.iter().enumerate() {
+            let is_full = report.limits
+                .as_ref()
+                .is_some_and(|limits| limits.promotion_slots_available == 0);
+            
+            if is_full {
+                report.skipped.extend(plan.promote[index..].iter().cloned());
+                break;
+            }
+            
+            match self.api.promote_listing(id).await {
+                Ok(_) | Err(Error::DryRun(_)) => report.promoted.push(id.clone()),
+                Err(error) => report.failed.push((id.clone(), error)),
+            }
+            
+            self.refresh_limits(&mut report).await;
+        }
+        
+        report
+    }
+    
+    async fn refresh_limits(
+        &self,
+        report: &mut PromotionReport,
+    ) {
+        match self.api.classifieds_limits().await {
+            Ok(limits) => report.limits = Some(limits),
+            Err(error) => report.errors.push(error),
+        }
+    }
+}
+
+/// The changes needed to keep the highest ranked listings promoted.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PromotionPlan {
+    /// IDs of listings to promote, from highest to lowest rank.
+    pub promote: Vec<String>,
+    /// IDs of listings to demote.
+    pub demote: Vec<String>,
+}
+
+impl PromotionPlan {
+    /// Computes the promotions and demotions needed to keep the highest ranked listings
+    /// promoted. The number of promotion slots is the number of currently promoted listings plus
+    /// the available promotion slots, capped at `max_promoted`. Archived listings are never
+    /// promoted. Currently promoted listings are preferred over other listings with an equal rank
+    /// to avoid needless changes.
+    pub fn new<F, K>(
+        listings: &[Listing],
+        limits: &ClassifiedsLimits,
+        max_promoted: Option<u32>,
+        rank: F,
+    ) -> Self
+    where
+        F: Fn(&Listing) -> Option<K>,
+        K: PartialOrd,
+    {
+        let promoted_count = listings
+            .iter()
+            .filter(|listing| listing.promoted)
+            .count();
+        let mut slots = promoted_count + limits.promotion_slots_available as usize;
+        
+        if let Some(max_promoted) = max_promoted {
+            slots = slots.min(max_promoted as usize);
+        }
+        
+        let mut ranked = listings
+            .iter()
+            .filter(|listing| !listing.archived)
+            .filter_map(|listing| rank(listing).map(|key| (key, listing)))
+            .collect::<Vec<_>>();
+        
+        ranked.sort_by(|(a_key, a), (b_key, b)| {
+            b_key.partial_cmp(a_key)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.promoted.cmp(&a.promoted))
+        });
+        ranked.truncate(slots);
+        
+        let promote = ranked
+            .iter()
+            .filter(|(_key, listing)| !listing.promoted)
+            .map(|(_key, listing)| listing.id.clone())
+            .collect();
+        let demote = listings
+            .iter()
+            .filter(|listing| listing.promoted)
+            .filter(|listing| !ranked.iter().any(|(_key, ranked)| ranked.id == listing.id))
+            .map(|listing| listing.id.clone())
+            .collect();
+        
+        Self {
+            promote,
+            demote,
+        }
+    }
+    
+    /// Whether the plan has no changes.
+    pub fn is_empty(&self) -> bool {
+        self.promote.is_empty() && self.demote.is_empty()
+    }
+}
+
+/// The results of applying a [`PromotionPlan`].
+#[derive(Debug)]
+pub struct PromotionReport {
+    /// IDs of listings which were promoted.
+    pub promoted: Vec<String>,
+    /// IDs of listings which were demoted.
+    pub demoted: Vec<String>,
+    /// IDs of listings which were not promoted because no promotion slots were available.
+    pub skipped: Vec<String>,
+    /// IDs of listings which failed to be promoted or demoted along with the error.
+    pub failed: Vec<(String, Error)>,
+    /// Errors re-checking the classifieds limits.
+    pub errors: Vec<Error>,
+    /// The most recently fetched classifieds limits.
+    pub limits: Option<ClassifiedsLimits>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::mock::{mock_api, mock_response};
+    use crate::response::listing::tests::get_listing;
+    use std::sync::{Arc, Mutex};
+    
+    fn get_limits(promotion_slots_available: u32) -> ClassifiedsLimits {
+        ClassifiedsLimits {
+            promotion_slots_available,
+            used: 0,
+            total: 100,
+            baseline: 100,
+            donation_bonus: 0,
+            gifted_premium_months_bonus: 0,
+            multiplier: 1,
+            twitter_follower_bonus: 0,
+            accepted_suggestion_bonus: 0,
+            mvp_donation_bonus: 0,
+            group_membership_bonus: 0,
+        }
+    }
+    
+    fn promoted_listing(id: &str, promoted: bool) -> Listing {
+        let mut listing = get_listing(id);
+        
+        listing.promoted = promoted;
+        listing
+    }
+    
+    fn rank(listing: &Listing) -> Option<u32> {
+        match listing.id.as_str() {
+            "never" => None,
+            id => id.parse().ok(),
+        }
+    }
+    
+    #[test]
+    fn promotes_highest_ranked_listings() {
+        let listings = [
+            promoted_listing("1", true),
+            promoted_listing("2", false),
+            promoted_listing("3", false),
+            promoted_listing("4", true),
+            promoted_listing("never", true),
+        ];
+        // 3 are promoted with 1 more available
+        let plan = PromotionPlan::new(&listings, &get_limits(1), None, rank);
+        
+        assert_eq!(plan.promote, vec!["3".to_string(), "2".to_string()]);
+        assert_eq!(plan.demote, vec!["never".to_string()]);
+        
+        let plan = PromotionPlan::new(&listings, &get_limits(1), Some(2), rank);
+        
+        assert_eq!(plan.promote, vec!["3".to_string()]);
+        assert_eq!(plan.demote, vec!["1".to_string(), "never".to_string()]);
+    }
+    
+    #[test]
+    fn rechecks_limits_after_each_change() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let api = mock_api({
+            let requests = Arc::clone(&requests);
+            let slots = Mutex::new(1);
+            
+            move |request| {
+                let path = request.url().path().to_string();
+                let mut slots = slots.lock().unwrap();
+                let response = match path.rsplit('/').next() {
+                    Some("demote") => {
+                        *slots += 1;
+                        mock_response(200, "null")
+                    },
+                    Some("promote") => {
+                        *slots -= 1;
+                        mock_response(200, include_str!("response/listing/fixtures/listing.json"))
+                    },
+                    _ => {
+                        let limits = serde_json::json!({ "listings": get_limits(*slots) });
+                        
+                        mock_response(200, &limits.to_string())
+                    },
+                };
+                
+                requests.lock().unwrap().push(path);
+                response
+            }
+        });
+        let plan = PromotionPlan {
+            promote: vec!["a".into(), "b".into(), "c".into()],
+            demote: vec!["x".into()],
+        };
+        let report = async_std::task::block_on(PromotionManager::new(&api, rank).apply(&plan));
+        
+        assert_eq!(report.demoted, vec!["x".to_string()]);
+        assert_eq!(report.promoted, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(report.skipped, vec!["c".to_string()]);
+        assert_eq!(report.limits.unwrap().promotion_slots_available, 0);
+        assert_eq!(*requests.lock().unwrap(), vec![
+            "/api/v2/classifieds/listings/x/demote",
+            "/api/classifieds/limits",
+            "/api/v2/classifieds/listings/a/promote",
+            "/api/classifieds/limits",
+            "/api/v2/classifieds/listings/b/promote",
+            "/api/classifieds/limits",
+        ]);
+    }
+    
+    #[test]
+    fn prefers_promoted_listings_with_equal_rank() {
+        let listings = [
+            promoted_listing("a", false),
+            promoted_listing("b", true),
+        ];
+        let plan = PromotionPlan::new(&listings, &get_limits(0), None, |_listing| Some(1));
+        
+        assert!(plan.is_empty());
+    }
+}
@@ -0,0 +1,431 @@
+//! Declarative listing reconciliation.
+
+use crate::{BackpackAPI, ListingIntent};
+use crate::error::Error;
+use crate::request::{BuyListingItem, CreateListing, UpdateListing};
+use crate::response::currencies::ResponseCurrencies;
+use crate::response::listing::{self, Listing};
+use serde::Serialize;
+
+/// The maximum difference between two currency values for them to be considered equal.
+const CURRENCY_EPSILON: f64 = 0.001;
+
+/// Reconciles the listings on backpack.tf with a desired set of listings.
+///
+/// The desired state is described as a list of [`CreateListing`] values. Sell listings are
+/// matched to existing listings by their item ID and buy listings are matched by the attributes
+/// of their item. Active listings which don't match a desired listing are deleted. Archived
+/// listings which don't match a desired listing are left as-is.
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::{BackpackAPI, Reconciler};
+/// use backpacktf_api::request::CreateListing;
+/// use tf2_price::{Currencies, ref_to_weps};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let backpacktf = BackpackAPI::builder()
+///         .token("token".into())
+///         .build();
+///     let reconciler = Reconciler::new(&backpacktf);
+///     let desired = vec![CreateListing::Sell {
+///         id: 7764221391,
+///         currencies: Currencies {
+///             keys: 1,
+///             weapons: ref_to_weps!(5),
+///         },
+///         details: None,
+///         buyout: true,
+///         offers: true,
+///     }];
+///     let plan = reconciler.plan(&desired).await?;
+///     let report = reconciler.apply(&plan).await;
+///
+///     println!("{} listings deleted", report.deleted);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Reconciler<'a> {
+    api: &'a BackpackAPI,
+}
+
+impl<'a> Reconciler<'a> {
+    /// Creates a new reconciler using the given API.
+    pub fn new(api: &'a BackpackAPI) -> Self {
+        Self {
+            api,
+        }
+    }
+    
+    /// Fetches the current listings and archived listings and computes the changes needed to
+    /// reach the desired listings. If the listings can't be fully fetched an error is returned,
+    /// since a partial state would cause existing listings to be recreated.
+    pub async fn plan<T>(
+        &self,
+        desired: &[CreateListing<T>],
+    ) -> Result<ReconcilePlan<T>, Error>
+    where
+        T: Serialize + Clone,
+    {
+        let (current, error) = self.api.get_all_listings_and_archived().await;
+        
+        if let Some(error) = error {
+            return Err(error);
+        }
+        
+        Ok(ReconcilePlan::new(desired, &current))
+    }
+    
+    /// Applies a plan using the chunked methods. Deletes are applied first to free up listing
+    /// slots, followed by updates, archive publishes, then creates. Errors from each step are
+    /// collected and do not stop the following steps.
+    pub async fn apply<'b, T>(
+        &self,
+        plan: &'b ReconcilePlan<T>,
+    ) -> ReconcileReport<'b, T>
+    where
+        T: Serialize,
+    {
+        let mut report = ReconcileReport {
+            deleted: 0,
+            archived_deleted: 0,
+            updated: Vec::new(),
+            archived_updated: Vec::new(),
+            published: Vec::new(),
+            created: Vec::new(),
+            errors: Vec::new(),
+        };
+        
+        if !plan.delete.is_empty() {
+            let (deleted, error) = self.api.delete_listings_chunked(&plan.delete).await;
+            
+            report.deleted = deleted;
+            report.errors.extend(error);
+        }
+        
+        if !plan.delete_archived.is_empty() {
+            let (deleted, error) = self.api.delete_archived_listings_chunked(&plan.delete_archived).await;
+            
+            report.archived_deleted = deleted;
+            report.errors.extend(error);
+        }
+        
+        if !plan.update.is_empty() {
+            let (updated, error) = self.api.update_listings_chunked(&plan.update).await;
+            
+            report.updated = updated;
+            report.errors.extend(error);
+        }
+        
+        if !plan.update_archived.is_empty() {
+            let (updated, error) = self.api.update_archived_listings_chunked(&plan.update_archived).await;
+            
+            report.archived_updated = updated;
+            report.errors.extend(error);
+        }
+        
+        if !plan.publish.is_empty() {
+            let (published, error) = self.api.publish_archived_listings_chunked(&plan.publish).await;
+            
+            report.published = published;
+            report.errors.extend(error);
+        }
+        
+        if !plan.create.is_empty() {
+            let (created, error) = self.api.create_listings_chunked(&plan.create).await;
+            
+            report.created = created;
+            report.errors.extend(error);
+        }
+        
+        report
+    }
+}
+
+/// The changes needed to reconcile the current listings with the desired listings.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ReconcilePlan<T> {
+    /// Listings to create.
+    pub create: Vec<CreateListing<T>>,
+    /// Active listings to update with a new price or details.
+    pub update: Vec<UpdateListing<T>>,
+    /// Archived listings to update with a new price or details before they are published.
+    pub update_archived: Vec<UpdateListing<T>>,
+    /// IDs of archived listings to publish.
+    pub publish: Vec<String>,
+    /// IDs of active listings to delete.
+    pub delete: Vec<String>,
+    /// IDs of archived listings to delete. Only archived listings which must be recreated to
+    /// change their buyout or offers settings are deleted.
+    pub delete_archived: Vec<String>,
+}
+
+impl<T> ReconcilePlan<T>
+where
+    T: Serialize + Clone,
+{
+    /// Computes the changes needed to reconcile the current listings with the desired listings.
+    /// Active listings are preferred over archived listings when both match a desired listing.
+    pub fn new(
+        desired: &[CreateListing<T>],
+        current: &[Listing],
+    ) -> Self {
+        let mut plan = Self {
+            create: Vec::new(),
+            update: Vec::new(),
+            update_archived: Vec::new(),
+            publish: Vec::new(),
+            delete: Vec::new(),
+            delete_archived: Vec::new(),
+        };
+        let mut claimed = vec![false; current.len()];
+        
+        for listing in desired {
+            let index = current
+                .iter()
+                .enumerate()
+                .filter(|(index, current)| !claimed[*index] && matches_listing(listing, current))
+                .min_by_key(|(_index, current)| current.archived)
+                .map(|(index, _current)| index);
+            let Some(index) = index else {
+                plan.create.push(listing.clone());
+                continue;
+            };
+            let current = &current[index];
+            let (currencies, details, buyout, offers) = match listing {
+                CreateListing::Sell { currencies, details, buyout, offers, .. } => (currencies, details, buyout, offers),
+                CreateListing::Buy { currencies, details, buyout, offers, .. } => (currencies, details, buyout, offers),
+            };
+            
+            claimed[index] = true;
+            
+            if current.buyout_only != *buyout || current.trade_offers_preferred != *offers {
+                // These can't be updated so the listing must be recreated
+                if current.archived {
+                    plan.delete_archived.push(current.id.clone());
+                } else {
+                    plan.delete.push(current.id.clone());
+                }
+                
+                plan.create.push(listing.clone());
+                continue;
+            }
+            
+            if !currencies_eq(currencies, &current.currencies) || !details_eq(details, &current.details) {
+                let update = UpdateListing {
+                    id: current.id.clone(),
+                    currencies: currencies.clone(),
+                    details: details.clone(),
+                };
+                
+                if current.archived {
+                    plan.update_archived.push(update);
+                } else {
+                    plan.update.push(update);
+                }
+            }
+            
+            if current.archived {
+                plan.publish.push(current.id.clone());
+            }
+        }
+        
+        for (current, claimed) in current.iter().zip(claimed) {
+            if !claimed && !current.archived {
+                plan.delete.push(current.id.clone());
+            }
+        }
+        
+        plan
+    }
+}
+
+impl<T> ReconcilePlan<T> {
+    /// Whether the plan has no changes.
+    pub fn is_empty(&self) -> bool {
+        self.create.is_empty()
+            && self.update.is_empty()
+            && self.update_archived.is_empty()
+            && self.publish.is_empty()
+            && self.delete.is_empty()
+            && self.delete_archived.is_empty()
+    }
+}
+
+/// The results of applying a [`ReconcilePlan`].
+#[derive(Debug)]
+pub struct ReconcileReport<'a, T> {
+    /// The number of active listings deleted.
+    pub deleted: u32,
+    /// The number of archived listings deleted.
+    pub archived_deleted: u32,
+    /// The results of updating active listings.
+    pub updated: Vec<listing::update_listing::Result<'a, T>>,
+    /// The results of updating archived listings.
+    pub archived_updated: Vec<listing::update_listing::Result<'a, T>>,
+    /// The results of publishing archived listings.
+    pub published: Vec<listing::archived_listing::Result<'a>>,
+    /// The results of creating listings.
+    pub created: Vec<listing::create_listing::Result<'a, T>>,
+    /// Errors which stopped a chunked step before it completed.
+    pub errors: Vec<Error>,
+}
+
+/// Whether an existing listing is for the same item as a desired listing.
+fn matches_listing<T>(
+    desired: &CreateListing<T>,
+    current: &Listing,
+) -> bool {
+    match desired {
+        CreateListing::Sell { id, .. } => {
+            current.intent == ListingIntent::Sell && current.item.id == Some(*id)
+        },
+        CreateListing::Buy { item, .. } => {
+            current.intent == ListingIntent::Buy && matches_item(item, &current.item)
+        },
+    }
+}
+
+/// Whether the attributes of a buy listing item match the item of an existing listing.
+fn matches_item(
+    desired: &BuyListingItem,
+    current: &listing::Item,
+) -> bool {
+    BuyListingItem::try_from(current).is_ok_and(|current| current == *desired)
+}
+
+/// Whether the desired currencies are equal to the currencies of an existing listing. The desired
+/// currencies are compared by their "keys" and "metal" fields.
+fn currencies_eq<T>(
+    desired: &T,
+    current: &ResponseCurrencies,
+) -> bool
+where
+    T: Serialize,
+{
+    let ResponseCurrencies::InGame(current) = current else {
+        return false;
+    };
+    let Ok(serde_json::Value::Object(desired)) = serde_json::to_value(desired) else {
+        return false;
+    };
+    let get = |name: &str| desired
+        .get(name)
+        .and_then(|value| value.as_f64())
+        .unwrap_or_default();
+    
+    (get("keys") - f64::from(current.keys)).abs() < CURRENCY_EPSILON
+        && (get("metal") - f64::from(current.metal)).abs() < CURRENCY_EPSILON
+}
+
+/// Whether the desired details are equal to the details of an existing listing. Missing details
+/// are equal to empty details.
+fn details_eq(
+    desired: &Option<String>,
+    current: &Option<String>,
+) -> bool {
+    desired.as_deref().unwrap_or_default() == current.as_deref().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tf2_price::{Currencies, ref_to_weps};
+    use tf2_enum::Quality;
+    
+    fn get_listings() -> (Listing, Listing) {
+        let buy: Listing = serde_json::from_str(include_str!("response/listing/fixtures/listing.json")).unwrap();
+        let mut sell = buy.clone();
+        
+        sell.id = "440_7764221391".into();
+        sell.intent = ListingIntent::Sell;
+        sell.item.id = Some(7764221391);
+        (buy, sell)
+    }
+    
+    #[test]
+    fn plans_changes() {
+        let (buy, sell) = get_listings();
+        let mut archived = sell.clone();
+        let mut stale = sell.clone();
+        
+        archived.id = "440_1".into();
+        archived.archived = true;
+        archived.item.id = Some(1);
+        stale.id = "440_2".into();
+        stale.item.id = Some(2);
+        
+        let currencies = Currencies {
+            keys: 2,
+            weapons: ref_to_weps!(2),
+        };
+        let desired = vec![
+            // matches the buy listing exactly
+            CreateListing::Buy {
+                item: BuyListingItem::new(30998, Quality::Unique).craftable(true),
+                currencies,
+                details: buy.details.clone(),
+                buyout: true,
+                offers: true,
+            },
+            // price changed
+            CreateListing::Sell {
+                id: 7764221391,
+                currencies: Currencies {
+                    keys: 3,
+                    weapons: 0,
+                },
+                details: sell.details.clone(),
+                buyout: true,
+                offers: true,
+            },
+            // archived with the same price
+            CreateListing::Sell {
+                id: 1,
+                currencies,
+                details: archived.details.clone(),
+                buyout: true,
+                offers: true,
+            },
+            // new listing
+            CreateListing::Sell {
+                id: 3,
+                currencies,
+                details: None,
+                buyout: true,
+                offers: true,
+            },
+        ];
+        let plan = ReconcilePlan::new(&desired, &[buy, sell, archived, stale]);
+        
+        assert_eq!(plan.create, vec![desired[3].clone()]);
+        assert_eq!(plan.update.len(), 1);
+        assert_eq!(plan.update[0].id, "440_7764221391");
+        assert!(plan.update_archived.is_empty());
+        assert_eq!(plan.publish, vec!["440_1".to_string()]);
+        assert_eq!(plan.delete, vec!["440_2".to_string()]);
+        assert!(plan.delete_archived.is_empty());
+    }
+    
+    #[test]
+    fn recreates_listing_when_offers_change() {
+        let (_buy, sell) = get_listings();
+        let desired = vec![CreateListing::Sell {
+            id: 7764221391,
+            currencies: Currencies {
+                keys: 2,
+                weapons: ref_to_weps!(2),
+            },
+            details: sell.details.clone(),
+            buyout: true,
+            offers: false,
+        }];
+        let plan = ReconcilePlan::new(&desired, &[sell]);
+        
+        assert_eq!(plan.delete, vec!["440_7764221391".to_string()]);
+        assert_eq!(plan.create, desired);
+        assert!(plan.update.is_empty());
+    }
+}
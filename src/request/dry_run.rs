@@ -0,0 +1,112 @@
+//! Requests recorded in dry-run mode.
+
+use std::fmt;
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::Value;
+
+/// The value used in place of the token in recorded requests.
+const REDACTED: &str = "[redacted]";
+
+/// A request which would have been sent if the client was not in dry-run mode. See
+/// [`BackpackAPIBuilder::dry_run`](crate::BackpackAPIBuilder::dry_run).
+///
+/// The query and body are the JSON produced by the request's parameters. Tokens are redacted so
+/// that recorded requests can be shared safely.
+#[derive(PartialEq, Clone, Debug)]
+pub struct DryRunRequest {
+    /// The HTTP method of the request.
+    pub method: Method,
+    /// The URI of the request.
+    pub uri: String,
+    /// The query string parameters of the request, if any.
+    pub query: Option<Value>,
+    /// The JSON body of the request, if any.
+    pub body: Option<Value>,
+}
+
+impl DryRunRequest {
+    /// Creates a new request from the given parameters. Use `&()` for an empty query or body.
+    pub(crate) fn new<Q, B>(
+        method: Method,
+        uri: String,
+        query: &Q,
+        body: &B,
+    ) -> Result<Self, serde_json::Error>
+    where
+        Q: Serialize,
+        B: Serialize,
+    {
+        Ok(Self {
+            method,
+            uri,
+            query: to_redacted_value(query)?,
+            body: to_redacted_value(body)?,
+        })
+    }
+}
+
+impl fmt::Display for DryRunRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.uri)?;
+        
+        if let Some(query) = &self.query {
+            write!(f, " query={query}")?;
+        }
+        
+        if let Some(body) = &self.body {
+            write!(f, " body={body}")?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// Converts parameters into a JSON value, redacting the token.
+fn to_redacted_value<T>(value: &T) -> Result<Option<Value>, serde_json::Error>
+where
+    T: Serialize,
+{
+    // Round trip through a string so that floats match the JSON that would be sent
+    let mut value: Value = serde_json::from_str(&serde_json::to_string(value)?)?;
+    
+    if let Value::Object(map) = &mut value {
+        if let Some(token) = map.get_mut("token") {
+            *token = Value::String(REDACTED.into());
+        }
+    }
+    
+    if value.is_null() {
+        Ok(None)
+    } else {
+        Ok(Some(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn redacts_token() {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            token: &'a str,
+            skip: u32,
+        }
+        
+        let request = DryRunRequest::new(
+            Method::DELETE,
+            "https://api.backpack.tf/api/classifieds/delete/v1".into(),
+            &(),
+            &Params {
+                token: "secret",
+                skip: 1,
+            },
+        ).unwrap();
+        
+        assert_eq!(request.query, None);
+        assert_eq!(request.body, Some(json!({ "token": REDACTED, "skip": 1 })));
+    }
+}
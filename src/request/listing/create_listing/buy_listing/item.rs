@@ -129,6 +129,66 @@ impl Item {
         self
     }
     
+    /// Builds the full name of the item as it appears on backpack.tf from its attributes e.g.
+    /// "Non-Craftable Strange Professional Killstreak Pain Train". The item's name is not an
+    /// attribute of the item, so `base_name` is the name the item has when unique, including the
+    /// skin's name for decorated weapons e.g. "The Wilson Weave". The leading "The" is dropped
+    /// when the name has any prefixes. Likewise, `effect_name` is the name of the item's unusual
+    /// effect e.g. "Burning Flames", which replaces the quality's name in the full name. It is
+    /// only used when the item has a particle.
+    pub fn full_name(
+        &self,
+        base_name: &str,
+        effect_name: Option<&str>,
+    ) -> String {
+        let effect_name = effect_name.filter(|_| self.particle.is_some());
+        let mut name = String::new();
+        
+        if !self.craftable {
+            name.push_str("Non-Craftable ");
+        }
+        
+        if self.strange && self.quality != Quality::Strange {
+            name.push_str("Strange ");
+        }
+        
+        match self.quality {
+            // These qualities are not shown in names
+            Quality::Unique | Quality::DecoratedWeapon => {},
+            // The effect is shown in place of the quality
+            Quality::Unusual if effect_name.is_some() => {},
+            quality => name.push_str(&format!("{quality} ")),
+        }
+        
+        if let Some(effect_name) = effect_name {
+            name.push_str(&format!("{effect_name} "));
+        }
+        
+        if self.festivized {
+            name.push_str("Festivized ");
+        }
+        
+        if let Some(killstreak_tier) = self.killstreak_tier {
+            name.push_str(&format!("{killstreak_tier} "));
+        }
+        
+        if self.australium {
+            name.push_str("Australium ");
+        }
+        
+        if name.is_empty() {
+            name.push_str(base_name);
+        } else {
+            name.push_str(base_name.strip_prefix("The ").unwrap_or(base_name));
+        }
+        
+        if let Some(wear) = self.wear {
+            name.push_str(&format!(" ({wear})"));
+        }
+        
+        name
+    }
+    
     fn compute_attribute_len(&self) -> usize {
         self.killstreak_tier.is_some() as usize
             + self.particle.is_some() as usize
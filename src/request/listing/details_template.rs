@@ -0,0 +1,495 @@
+//! Templates for listing details.
+
+use super::CreateListing;
+use super::validation::MAX_DETAILS_LENGTH;
+use crate::ListingIntent;
+use crate::error::{ParseTemplateError, RenderTemplateError};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use tf2_price::FloatCurrencies;
+
+/// A template for the details of a listing.
+///
+/// Placeholders are written as `{name}` and replaced with their value when rendered. The
+/// built-in placeholders are:
+/// - `{price}` - The listing's currencies e.g. "2 keys, 5.11 ref".
+/// - `{keys}` - The number of keys.
+/// - `{metal}` - The amount of metal in refined e.g. "5.11".
+/// - `{intent}` - "Buy" or "Sell".
+/// - `{item_name}` - The item name given in the [`DetailsContext`].
+/// - `{stock}` - The stock given in the [`DetailsContext`].
+///
+/// `{item_name}` and `{stock}` render as empty when they aren't given in the [`DetailsContext`].
+/// Any other placeholder is looked up in the variables of the [`DetailsContext`] and rendering
+/// returns [`RenderTemplateError::UnknownPlaceholder`] if it isn't there.
+///
+/// Sections are written as `{#name}...{/name}` and are only rendered when the value is present
+/// and not empty or zero. Inverted sections written as `{^name}...{/name}` are only rendered
+/// when it isn't. Unlike placeholders, sections treat variables which are not in the
+/// [`DetailsContext`] as empty. The `buy` and `sell` values can be used in sections to check the
+/// intent of the listing. Use `{{` and `}}` for literal braces.
+///
+/// # Examples
+/// ```
+/// use backpacktf_api::request::{BuyListingItem, CreateListing, DetailsContext, DetailsTemplate};
+/// use tf2_price::{Currencies, ref_to_weps};
+/// use tf2_enum::Quality;
+///
+/// let template = DetailsTemplate::new(
+///     "{#buy}Buying{/buy}{#sell}Selling{/sell} {item_name} for {price}!{#stock} {stock} in stock.{/stock}",
+/// ).unwrap();
+/// let listing = CreateListing::Buy {
+///     item: BuyListingItem::new(1071, Quality::Strange),
+///     currencies: Currencies { keys: 2, weapons: ref_to_weps!(5) },
+///     details: None,
+///     buyout: true,
+///     offers: true,
+/// };
+/// let context = DetailsContext::new()
+///     .item_name("Strange Golden Frying Pan")
+///     .stock(0);
+///
+/// assert_eq!(
+///     template.render(&listing, &context).unwrap(),
+///     "Buying Strange Golden Frying Pan for 2 keys, 5 ref!",
+/// );
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DetailsTemplate {
+    source: String,
+    nodes: Vec<Node>,
+}
+
+/// Values used when rendering a [`DetailsTemplate`] which can't be derived from the listing.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct DetailsContext {
+    /// The full name of the item.
+    pub item_name: Option<String>,
+    /// The number of the item in stock.
+    pub stock: Option<u32>,
+    /// Additional values for custom placeholders.
+    pub variables: HashMap<String, String>,
+}
+
+impl DetailsContext {
+    /// Creates a new empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// The full name of the item.
+    pub fn item_name<S>(mut self, item_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.item_name = Some(item_name.into());
+        self
+    }
+    
+    /// The number of the item in stock.
+    pub fn stock(mut self, stock: u32) -> Self {
+        self.stock = Some(stock);
+        self
+    }
+    
+    /// Adds a value for a custom placeholder.
+    pub fn variable<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Node {
+    Text(String),
+    Placeholder(String),
+    Section {
+        name: String,
+        inverted: bool,
+        nodes: Vec<Node>,
+    },
+}
+
+/// A resolved placeholder value.
+enum Value {
+    Text(String),
+    Number(f32),
+    Flag(bool),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Text(text) => !text.is_empty(),
+            Value::Number(number) => *number != 0.0,
+            Value::Flag(flag) => *flag,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Text(text) => write!(f, "{text}"),
+            Value::Number(number) if number.fract() == 0.0 => write!(f, "{number}"),
+            Value::Number(number) => {
+                let number = format!("{number:.2}");
+                
+                // Values which round to a whole number e.g. "5.00" have no decimal places left
+                write!(f, "{}", number.trim_end_matches('0').trim_end_matches('.'))
+            },
+            Value::Flag(flag) => write!(f, "{flag}"),
+        }
+    }
+}
+
+/// The values available when rendering a template.
+struct Scope<'a> {
+    intent: ListingIntent,
+    currencies: FloatCurrencies,
+    context: &'a DetailsContext,
+}
+
+impl Scope<'_> {
+    fn get(&self, name: &str) -> Result<Option<Value>, RenderTemplateError> {
+        let value = match name {
+            "price" => Value::Text(self.currencies.to_string()),
+            "keys" => Value::Number(self.currencies.keys),
+            "metal" => Value::Number(self.currencies.metal),
+            "intent" => Value::Text(self.intent.to_string()),
+            "buy" => Value::Flag(self.intent == ListingIntent::Buy),
+            "sell" => Value::Flag(self.intent == ListingIntent::Sell),
+            "item_name" => match &self.context.item_name {
+                Some(item_name) => Value::Text(item_name.clone()),
+                None => return Ok(None),
+            },
+            "stock" => match self.context.stock {
+                Some(stock) => Value::Number(stock as f32),
+                None => return Ok(None),
+            },
+            _ => match self.context.variables.get(name) {
+                Some(value) => Value::Text(value.clone()),
+                None => return Err(RenderTemplateError::UnknownPlaceholder(name.into())),
+            },
+        };
+        
+        Ok(Some(value))
+    }
+}
+
+impl DetailsTemplate {
+    /// Parses a template.
+    pub fn new(template: &str) -> Result<Self, ParseTemplateError> {
+        Ok(Self {
+            source: template.into(),
+            nodes: parse(template)?,
+        })
+    }
+    
+    /// The source of the template.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+    
+    /// Renders the template for a listing. Returns an error if the rendered details exceed
+    /// [`MAX_DETAILS_LENGTH`].
+    pub fn render<T>(
+        &self,
+        listing: &CreateListing<T>,
+        context: &DetailsContext,
+    ) -> Result<String, RenderTemplateError>
+    where
+        T: Serialize,
+    {
+        let (intent, currencies) = match listing {
+            CreateListing::Sell { currencies, .. } => (ListingIntent::Sell, currencies),
+            CreateListing::Buy { currencies, .. } => (ListingIntent::Buy, currencies),
+        };
+        let currencies = serde_json::to_value(currencies)
+            .and_then(serde_json::from_value::<FloatCurrencies>)
+            .map_err(|_| RenderTemplateError::InvalidCurrencies)?;
+        let scope = Scope {
+            intent,
+            currencies,
+            context,
+        };
+        let mut details = String::new();
+        
+        render_nodes(&self.nodes, &scope, &mut details)?;
+        
+        let length = details.chars().count();
+        
+        if length > MAX_DETAILS_LENGTH {
+            return Err(RenderTemplateError::DetailsTooLong {
+                length,
+                max: MAX_DETAILS_LENGTH,
+            });
+        }
+        
+        Ok(details)
+    }
+    
+    /// Renders the template for a listing and sets the result as the listing's details.
+    pub fn apply<T>(
+        &self,
+        listing: &mut CreateListing<T>,
+        context: &DetailsContext,
+    ) -> Result<(), RenderTemplateError>
+    where
+        T: Serialize,
+    {
+        let rendered = self.render(listing, context)?;
+        let details = match listing {
+            CreateListing::Sell { details, .. } => details,
+            CreateListing::Buy { details, .. } => details,
+        };
+        
+        *details = Some(rendered);
+        Ok(())
+    }
+}
+
+impl FromStr for DetailsTemplate {
+    type Err = ParseTemplateError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for DetailsTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Serialize for DetailsTemplate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for DetailsTemplate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let s = String::deserialize(deserializer)?;
+        
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    scope: &Scope<'_>,
+    output: &mut String,
+) -> Result<(), RenderTemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Placeholder(name) => if let Some(value) = scope.get(name)? {
+                output.push_str(&value.to_string());
+            },
+            Node::Section { name, inverted, nodes } => {
+                let value = match scope.get(name) {
+                    // Variables which aren't given are treated as empty
+                    Err(RenderTemplateError::UnknownPlaceholder(_)) => None,
+                    result => result?,
+                };
+                let is_truthy = value.is_some_and(|value| value.is_truthy());
+                
+                if is_truthy != *inverted {
+                    render_nodes(nodes, scope, output)?;
+                }
+            },
+        }
+    }
+    
+    Ok(())
+}
+
+/// The name and whether the section is inverted of an open section, or `None` for the root,
+/// along with the nodes parsed so far.
+type Frame = (Option<(String, bool)>, Vec<Node>);
+
+fn parse(template: &str) -> Result<Vec<Node>, ParseTemplateError> {
+    // The root nodes are at the bottom of the stack with any open sections above them.
+    let mut stack: Vec<Frame> = vec![(None, Vec::new())];
+    let mut text = String::new();
+    let mut chars = template.chars().enumerate().peekable();
+    
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '{' if chars.next_if(|(_, c)| *c == '{').is_some() => text.push('{'),
+            '}' if chars.next_if(|(_, c)| *c == '}').is_some() => text.push('}'),
+            '}' => return Err(ParseTemplateError::UnmatchedBrace { position }),
+            '{' => {
+                let mut tag = String::new();
+                
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, '{')) | None => {
+                            return Err(ParseTemplateError::UnclosedPlaceholder { position });
+                        },
+                        Some((_, c)) => tag.push(c),
+                    }
+                }
+                
+                let (kind, name) = match tag.chars().next() {
+                    Some(kind @ ('#' | '^' | '/')) => (Some(kind), tag[1..].trim()),
+                    _ => (None, tag.trim()),
+                };
+                
+                if name.is_empty() {
+                    return Err(ParseTemplateError::EmptyPlaceholder { position });
+                }
+                
+                let nodes = &mut stack.last_mut().expect("Stack is never empty").1;
+                
+                if !text.is_empty() {
+                    nodes.push(Node::Text(std::mem::take(&mut text)));
+                }
+                
+                match kind {
+                    None => nodes.push(Node::Placeholder(name.into())),
+                    Some('/') => {
+                        let is_open = matches!(
+                            stack.last(),
+                            Some((Some((open, _)), _)) if open == name,
+                        );
+                        
+                        if !is_open {
+                            return Err(ParseTemplateError::UnexpectedSectionEnd {
+                                name: name.into(),
+                                position,
+                            });
+                        }
+                        
+                        if let Some((Some((name, inverted)), nodes)) = stack.pop() {
+                            stack.last_mut().expect("Stack is never empty").1.push(Node::Section {
+                                name,
+                                inverted,
+                                nodes,
+                            });
+                        }
+                    },
+                    Some(kind) => stack.push((Some((name.into(), kind == '^')), Vec::new())),
+                }
+            },
+            c => text.push(c),
+        }
+    }
+    
+    let (section, mut nodes) = stack.pop().expect("Stack is never empty");
+    
+    if let Some((name, _)) = section {
+        return Err(ParseTemplateError::UnclosedSection { name });
+    }
+    
+    if !text.is_empty() {
+        nodes.push(Node::Text(text));
+    }
+    
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::BuyListingItem;
+    use tf2_price::{Currencies, ref_to_weps};
+    use tf2_enum::Quality;
+    
+    fn sell_listing() -> CreateListing<Currencies> {
+        CreateListing::Sell {
+            id: 1,
+            currencies: Currencies { keys: 0, weapons: ref_to_weps!(5.11) },
+            details: None,
+            buyout: true,
+            offers: true,
+        }
+    }
+    
+    #[test]
+    fn renders_placeholders_and_sections() {
+        let template = DetailsTemplate::new("{intent}ing {{{keys}}} {metal}{^stock} none left{/stock}{#owner} from {owner}{/owner}").unwrap();
+        let context = DetailsContext::new()
+            .variable("owner", "me");
+        
+        assert_eq!(template.render(&sell_listing(), &context).unwrap(), "Selling {0} 5.11 none left from me");
+    }
+    
+    #[test]
+    fn trims_numbers_which_round_to_whole_numbers() {
+        assert_eq!(Value::Number(4.999).to_string(), "5");
+        assert_eq!(Value::Number(0.001).to_string(), "0");
+        assert_eq!(Value::Number(5.5).to_string(), "5.5");
+        assert_eq!(Value::Number(5.11).to_string(), "5.11");
+    }
+    
+    #[test]
+    fn treats_absent_section_variables_as_empty() {
+        let template = DetailsTemplate::new("{#note}Note: {note}{/note}{^note}No note{/note}").unwrap();
+        
+        assert_eq!(template.render(&sell_listing(), &DetailsContext::new()).unwrap(), "No note");
+        assert_eq!(
+            template.render(&sell_listing(), &DetailsContext::new().variable("note", "hi")).unwrap(),
+            "Note: hi",
+        );
+    }
+    
+    #[test]
+    fn applies_rendered_details() {
+        let template: DetailsTemplate = "{item_name}".parse().unwrap();
+        let mut listing = CreateListing::Buy {
+            item: BuyListingItem::new(5021, Quality::Unique),
+            currencies: Currencies { keys: 1, weapons: 0 },
+            details: None,
+            buyout: true,
+            offers: true,
+        };
+        
+        template.apply(&mut listing, &DetailsContext::new().item_name("Mann Co. Supply Crate Key")).unwrap();
+        
+        assert!(matches!(listing, CreateListing::Buy { details: Some(details), .. } if details == "Mann Co. Supply Crate Key"));
+    }
+    
+    #[test]
+    fn rejects_invalid_templates() {
+        assert_eq!(DetailsTemplate::new("{price"), Err(ParseTemplateError::UnclosedPlaceholder { position: 0 }));
+        assert_eq!(DetailsTemplate::new("a}"), Err(ParseTemplateError::UnmatchedBrace { position: 1 }));
+        assert_eq!(DetailsTemplate::new("{}"), Err(ParseTemplateError::EmptyPlaceholder { position: 0 }));
+        assert_eq!(DetailsTemplate::new("{#buy}"), Err(ParseTemplateError::UnclosedSection { name: "buy".into() }));
+        assert_eq!(DetailsTemplate::new("{#buy}{/sell}"), Err(ParseTemplateError::UnexpectedSectionEnd {
+            name: "sell".into(),
+            position: 6,
+        }));
+    }
+    
+    #[test]
+    fn rejects_unknown_placeholders_and_long_details() {
+        let context = DetailsContext::new()
+            .variable("long", "a".repeat(MAX_DETAILS_LENGTH + 1));
+        let unknown = DetailsTemplate::new("{missing}").unwrap();
+        let long = DetailsTemplate::new("{long}").unwrap();
+        
+        assert_eq!(unknown.render(&sell_listing(), &context), Err(RenderTemplateError::UnknownPlaceholder("missing".into())));
+        // Built-in placeholders which aren't given are empty rather than unknown
+        assert_eq!(DetailsTemplate::new("{item_name}{stock}").unwrap().render(&sell_listing(), &context).unwrap(), "");
+        assert_eq!(long.render(&sell_listing(), &context), Err(RenderTemplateError::DetailsTooLong {
+            length: MAX_DETAILS_LENGTH + 1,
+            max: MAX_DETAILS_LENGTH,
+        }));
+    }
+}
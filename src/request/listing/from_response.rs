@@ -0,0 +1,361 @@
+//! Conversions from response listings into request listings.
+
+use super::{CreateListing, UpdateListing};
+use super::create_listing::buy_listing::Item as BuyListingItem;
+use crate::ListingIntent;
+use crate::error::TryFromListingError;
+use crate::response::listing::{Item, Listing};
+use crate::response::snapshot::Item as SnapshotItem;
+use tf2_enum::{AttributeSet, Killstreaker, Sheen, Spell, SpellSet, StrangePart, StrangePartSet};
+use tf2_enum::error::InsertError;
+use tf2_price::Currencies;
+
+/// An attribute of a response item which is lost when converting it into a [`BuyListingItem`].
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum LostAttribute {
+    /// The sheen of a professional killstreak item.
+    Sheen(Sheen),
+    /// The killstreaker of a professional killstreak item.
+    Killstreaker(Killstreaker),
+    /// The crate series.
+    CrateSeries(u8),
+    /// A spell which did not fit in the item's spells.
+    Spell(Spell),
+    /// A strange part which did not fit in the item's strange parts.
+    StrangePart(StrangePart),
+    /// The recipe of a chemistry set or strangifier.
+    Recipe,
+}
+
+impl Item {
+    /// Gets the attributes of the item which are lost when converting it into a
+    /// [`BuyListingItem`].
+    pub fn lost_attributes(&self) -> Vec<LostAttribute> {
+        let mut lost = Vec::new();
+        
+        if let Some(sheen) = self.sheen {
+            lost.push(LostAttribute::Sheen(sheen));
+        }
+        
+        if let Some(killstreaker) = self.killstreaker {
+            lost.push(LostAttribute::Killstreaker(killstreaker));
+        }
+        
+        if let Some(crate_series) = self.crate_series {
+            lost.push(LostAttribute::CrateSeries(crate_series));
+        }
+        
+        if self.recipe.is_some() {
+            lost.push(LostAttribute::Recipe);
+        }
+        
+        lost.extend(collect_spells(self).1.into_iter().map(LostAttribute::Spell));
+        lost.extend(collect_strange_parts(self).1.into_iter().map(LostAttribute::StrangePart));
+        lost
+    }
+}
+
+impl SnapshotItem {
+    /// Gets the attributes of the item which are lost when converting it into a
+    /// [`BuyListingItem`].
+    pub fn lost_attributes(&self) -> Vec<LostAttribute> {
+        let mut lost = Vec::new();
+        
+        if let Some(sheen) = self.get_sheen() {
+            lost.push(LostAttribute::Sheen(sheen));
+        }
+        
+        if let Some(killstreaker) = self.get_killstreaker() {
+            lost.push(LostAttribute::Killstreaker(killstreaker));
+        }
+        
+        let (_, lost_spells) = collect_spell_set(self.get_spells().into_iter().flatten());
+        let (_, lost_strange_parts) = collect_strange_part_set(self.get_strange_parts().into_iter().flatten());
+        
+        lost.extend(lost_spells.into_iter().map(LostAttribute::Spell));
+        lost.extend(lost_strange_parts.into_iter().map(LostAttribute::StrangePart));
+        lost
+    }
+}
+
+impl Listing {
+    /// Gets the attributes which are lost when converting the listing into a [`CreateListing`].
+    /// Sell listings reference the item by its ID so no attributes are lost.
+    pub fn lost_attributes(&self) -> Vec<LostAttribute> {
+        match self.intent {
+            ListingIntent::Sell => Vec::new(),
+            ListingIntent::Buy => self.item.lost_attributes(),
+        }
+    }
+}
+
+/// Converts a listing item into a buy listing item. Attributes which can't be represented are
+/// dropped; use [`Item::lost_attributes`] to check which attributes were lost.
+///
+/// # Examples
+/// ```
+/// use backpacktf_api::request::BuyListingItem;
+/// use backpacktf_api::response::listing::Listing;
+///
+/// let listing: Listing = serde_json::from_str(include_str!("../../response/listing/fixtures/spelled.json")).unwrap();
+/// let item = BuyListingItem::try_from(&listing.item).unwrap();
+///
+/// assert_eq!(item.defindex as i32, listing.item.defindex);
+/// assert!(listing.item.lost_attributes().is_empty());
+/// ```
+impl TryFrom<&Item> for BuyListingItem {
+    type Error = TryFromListingError;
+    
+    fn try_from(item: &Item) -> Result<Self, Self::Error> {
+        let defindex = u32::try_from(item.defindex)
+            .map_err(|_| TryFromListingError::NegativeDefindex(item.defindex))?;
+        
+        Ok(Self {
+            defindex,
+            quality: item.quality,
+            craftable: item.craftable,
+            killstreak_tier: item.killstreak_tier,
+            particle: item.particle.as_ref().map(|particle| particle.id),
+            wear: item.wear,
+            skin: item.texture.as_ref().map(|texture| texture.id),
+            strange: item.strange,
+            festivized: item.festivized,
+            australium: item.australium,
+            paint: item.paint,
+            spells: collect_spells(item).0,
+            strange_parts: collect_strange_parts(item).0,
+        })
+    }
+}
+
+/// Converts a snapshot item into a buy listing item using the item's decoded attributes.
+/// Attributes which can't be represented, including spells and strange parts which don't fit,
+/// are dropped; use [`SnapshotItem::lost_attributes`] to check which attributes were lost.
+///
+/// # Examples
+/// ```
+/// use backpacktf_api::request::BuyListingItem;
+/// use backpacktf_api::response::snapshot::Snapshot;
+/// use backpacktf_api::tf2_enum::Paint;
+///
+/// let snapshot: Snapshot = serde_json::from_str(include_str!("../../response/snapshot/fixtures/snapshot.json")).unwrap();
+/// let item = BuyListingItem::try_from(&snapshot.listings[0].item).unwrap();
+///
+/// assert_eq!(item.particle, Some(10));
+/// assert_eq!(item.paint, Some(Paint::PinkAsHell));
+/// ```
+impl TryFrom<&SnapshotItem> for BuyListingItem {
+    type Error = TryFromListingError;
+    
+    fn try_from(item: &SnapshotItem) -> Result<Self, Self::Error> {
+        let defindex = u32::try_from(item.defindex)
+            .map_err(|_| TryFromListingError::NegativeDefindex(item.defindex))?;
+        
+        Ok(Self {
+            defindex,
+            quality: item.get_quality(),
+            craftable: item.is_craftable(),
+            killstreak_tier: item.get_killstreak_tier(),
+            particle: item.get_particle_value(),
+            wear: item.get_wear(),
+            skin: item.get_skin_value(),
+            strange: item.is_strange(),
+            festivized: item.is_festivized(),
+            australium: item.is_australium(),
+            paint: item.get_paint(),
+            spells: collect_spell_set(item.get_spells().into_iter().flatten()).0,
+            strange_parts: collect_strange_part_set(item.get_strange_parts().into_iter().flatten()).0,
+        })
+    }
+}
+
+impl TryFrom<&Listing> for CreateListing<Currencies> {
+    type Error = TryFromListingError;
+    
+    fn try_from(listing: &Listing) -> Result<Self, Self::Error> {
+        let currencies = Currencies::try_from(listing.currencies)?;
+        let details = listing.details.clone();
+        let buyout = listing.buyout_only;
+        let offers = listing.trade_offers_preferred;
+        
+        match listing.intent {
+            ListingIntent::Sell => Ok(CreateListing::Sell {
+                id: listing.item.id.ok_or(TryFromListingError::MissingItemId)?,
+                currencies,
+                details,
+                buyout,
+                offers,
+            }),
+            ListingIntent::Buy => Ok(CreateListing::Buy {
+                item: BuyListingItem::try_from(&listing.item)?,
+                currencies,
+                details,
+                buyout,
+                offers,
+            }),
+        }
+    }
+}
+
+impl TryFrom<&Listing> for UpdateListing<Currencies> {
+    type Error = TryFromListingError;
+    
+    fn try_from(listing: &Listing) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: listing.id.clone(),
+            currencies: Currencies::try_from(listing.currencies)?,
+            details: listing.details.clone(),
+        })
+    }
+}
+
+/// Collects the spells of an item along with any spells which did not fit.
+fn collect_spells(item: &Item) -> (SpellSet, Vec<Spell>) {
+    collect_spell_set(item.spells.iter().flatten().map(|attribute| attribute.spell))
+}
+
+/// Collects spells into a set along with any spells which did not fit.
+fn collect_spell_set<I>(spells: I) -> (SpellSet, Vec<Spell>)
+where
+    I: IntoIterator<Item = Spell>,
+{
+    let mut set = SpellSet::default();
+    let mut lost = Vec::new();
+    
+    for spell in spells {
+        if let Err(InsertError::Full) = set.try_insert(spell) {
+            lost.push(spell);
+        }
+    }
+    
+    (set, lost)
+}
+
+/// Collects the strange parts of an item from its kill eaters along with any strange parts
+/// which did not fit. Kill eaters which are not strange parts such as the item's own kill count
+/// are skipped.
+fn collect_strange_parts(item: &Item) -> (StrangePartSet, Vec<StrangePart>) {
+    let strange_part_iter = item.kill_eaters
+        .iter()
+        .chain(item.strange_parts.iter())
+        .flatten()
+        .filter_map(|attribute| attribute.get_strange_part());
+    
+    collect_strange_part_set(strange_part_iter)
+}
+
+/// Collects strange parts into a set along with any strange parts which did not fit.
+fn collect_strange_part_set<I>(strange_parts: I) -> (StrangePartSet, Vec<StrangePart>)
+where
+    I: IntoIterator<Item = StrangePart>,
+{
+    let mut set = StrangePartSet::default();
+    let mut lost = Vec::new();
+    
+    for strange_part in strange_parts {
+        if let Err(InsertError::Full) = set.try_insert(strange_part) {
+            lost.push(strange_part);
+        }
+    }
+    
+    (set, lost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tf2_enum::Quality;
+    
+    #[test]
+    fn converts_buy_listing() {
+        let listing: Listing = serde_json::from_str(include_str!("../../response/listing/fixtures/Strange Massed Flies Crone's Dome.json")).unwrap();
+        let query = CreateListing::try_from(&listing).unwrap();
+        
+        if let CreateListing::Buy { item, currencies, .. } = query {
+            assert_eq!(item.quality, Quality::Unusual);
+            assert_eq!(item.particle, Some(12));
+            assert!(item.strange);
+            assert_eq!(Ok(currencies), Currencies::try_from(listing.currencies).map_err(|_| ()));
+        } else {
+            panic!("Expected buy listing");
+        }
+    }
+    
+    #[test]
+    fn converts_sell_listing() {
+        let listing: Listing = serde_json::from_str(include_str!("../../response/listing/fixtures/spelled.json")).unwrap();
+        let query = CreateListing::try_from(&listing).unwrap();
+        let update = UpdateListing::try_from(&listing).unwrap();
+        
+        assert!(matches!(query, CreateListing::Sell { id: 12531985792, .. }));
+        assert_eq!(update.id, listing.id);
+        assert!(listing.lost_attributes().is_empty());
+    }
+    
+    #[test]
+    fn reports_lost_attributes() {
+        let mut listing: Listing = serde_json::from_str(include_str!("../../response/listing/fixtures/listing.json")).unwrap();
+        
+        listing.item.sheen = Some(Sheen::TeamShine);
+        listing.item.crate_series = Some(1);
+        
+        assert_eq!(listing.lost_attributes(), vec![
+            LostAttribute::Sheen(Sheen::TeamShine),
+            LostAttribute::CrateSeries(1),
+        ]);
+    }
+    
+    #[test]
+    fn converts_snapshot_item() {
+        use crate::response::snapshot::Snapshot;
+        use tf2_enum::KillstreakTier;
+        
+        let snapshot: Snapshot = serde_json::from_str(include_str!("../../response/snapshot/fixtures/snapshot_strange_professional_killstreak_australium_rocket_launcher.json")).unwrap();
+        let listing = snapshot.listings.iter().find(|listing| listing.item.id == Some(11459331376)).unwrap();
+        let item = BuyListingItem::try_from(&listing.item).unwrap();
+        
+        assert_eq!(item.defindex, 205);
+        assert!(item.australium);
+        assert!(item.craftable);
+        assert_eq!(item.killstreak_tier, Some(KillstreakTier::Professional));
+        assert!(item.strange_parts.contains(&StrangePart::GibKills));
+        assert_eq!(item.paint, None);
+        assert_eq!(listing.item.lost_attributes(), vec![
+            LostAttribute::Sheen(Sheen::Manndarin),
+            LostAttribute::Killstreaker(Killstreaker::CerebralDischarge),
+        ]);
+    }
+    
+    #[test]
+    fn reports_snapshot_spells_which_do_not_fit() {
+        let item: SnapshotItem = serde_json::from_value(serde_json::json!({
+            "defindex": 200,
+            "quality": 6,
+            "attributes": [
+                { "defindex": 1006, "value": 1065353216, "float_value": 1 },
+                { "defindex": 1007, "value": 1065353216, "float_value": 1 },
+                { "defindex": 1009, "value": 1065353216, "float_value": 1 },
+            ],
+            "marketplace_price": null,
+            "marketplace_bot_steamid": null,
+            "marketplace_sku": null,
+            "marketplace_image": null,
+        })).unwrap();
+        let buy_item = BuyListingItem::try_from(&item).unwrap();
+        
+        assert_eq!(buy_item.spells.len(), 2);
+        assert_eq!(item.lost_attributes(), vec![LostAttribute::Spell(Spell::Exorcism)]);
+    }
+    
+    #[test]
+    fn negative_defindex_is_error() {
+        let mut listing: Listing = serde_json::from_str(include_str!("../../response/listing/fixtures/listing.json")).unwrap();
+        
+        listing.item.defindex = -100;
+        
+        assert!(matches!(
+            BuyListingItem::try_from(&listing.item),
+            Err(TryFromListingError::NegativeDefindex(-100))
+        ));
+    }
+}
@@ -3,6 +3,12 @@
 #[allow(clippy::module_inception)]
 pub mod create_listing;
 pub mod update_listing;
+pub mod validation;
+mod from_response;
+mod details_template;
 
 pub use create_listing::CreateListing;
-pub use update_listing::UpdateListing;
\ No newline at end of file
+pub use update_listing::UpdateListing;
+pub use validation::ValidationIssue;
+pub use from_response::LostAttribute;
+pub use details_template::{DetailsTemplate, DetailsContext};
\ No newline at end of file
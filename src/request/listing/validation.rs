@@ -0,0 +1,275 @@
+//! Local validation of listing requests.
+
+use super::{CreateListing, UpdateListing};
+use super::create_listing::buy_listing::Item;
+use serde::Serialize;
+use serde_json::Value;
+use tf2_enum::Paint;
+
+/// The maximum number of characters allowed in the details of a listing.
+pub const MAX_DETAILS_LENGTH: usize = 200;
+
+/// An issue found when validating a listing request locally.
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+pub enum ValidationIssue {
+    /// The currencies could not be read as an object of "keys" and/or "metal" values.
+    #[error("Currencies are not an object of currency values")]
+    InvalidCurrencies,
+    /// All currencies are zero or missing.
+    #[error("Currencies are zero")]
+    ZeroCurrencies,
+    /// A currency value is negative.
+    #[error("Currency `{}` is negative", .name)]
+    NegativeCurrency {
+        /// The name of the currency.
+        name: String,
+    },
+    /// The details exceed the maximum length.
+    #[error("Details are {} characters long which exceeds the maximum of {}", .length, .max)]
+    DetailsTooLong {
+        /// The number of characters in the details.
+        length: usize,
+        /// The maximum number of characters allowed.
+        max: usize,
+    },
+    /// The item ID of a sell listing is 0.
+    #[error("Item ID is 0")]
+    ZeroItemId,
+    /// The defindex of a buy listing item is 0.
+    #[error("Item defindex is 0")]
+    ZeroDefindex,
+    /// A paint was given for an item which can't be painted.
+    #[error("Item with {} can't be painted", .reason)]
+    Unpaintable {
+        /// The paint.
+        paint: Paint,
+        /// The attribute which makes the item unpaintable e.g. "a killstreak tier".
+        reason: &'static str,
+    },
+    /// The listing ID of an update is empty.
+    #[error("Listing ID is empty")]
+    EmptyListingId,
+}
+
+impl<T> CreateListing<T>
+where
+    T: Serialize,
+{
+    /// Validates the listing locally, returning any issues found. An empty list means no issues
+    /// were found, but the listing may still be rejected by backpack.tf.
+    ///
+    /// # Examples
+    /// ```
+    /// use backpacktf_api::request::{BuyListingItem, CreateListing, ValidationIssue};
+    /// use tf2_price::Currencies;
+    /// use tf2_enum::Quality;
+    ///
+    /// let listing = CreateListing::Buy {
+    ///     item: BuyListingItem::new(0, Quality::Unique),
+    ///     currencies: Currencies::default(),
+    ///     details: None,
+    ///     buyout: true,
+    ///     offers: true,
+    /// };
+    ///
+    /// assert_eq!(listing.validate(), vec![
+    ///     ValidationIssue::ZeroCurrencies,
+    ///     ValidationIssue::ZeroDefindex,
+    /// ]);
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let (currencies, details) = match self {
+            CreateListing::Sell { currencies, details, .. } => (currencies, details),
+            CreateListing::Buy { currencies, details, .. } => (currencies, details),
+        };
+        let mut issues = validate_currencies(currencies);
+        
+        issues.extend(validate_details(details));
+        
+        match self {
+            CreateListing::Sell { id, .. } => {
+                if *id == 0 {
+                    issues.push(ValidationIssue::ZeroItemId);
+                }
+            },
+            CreateListing::Buy { item, .. } => {
+                if item.defindex == 0 {
+                    issues.push(ValidationIssue::ZeroDefindex);
+                }
+                
+                if let Some(issue) = validate_paint(item) {
+                    issues.push(issue);
+                }
+            },
+        }
+        
+        issues
+    }
+}
+
+impl<T> UpdateListing<T>
+where
+    T: Serialize,
+{
+    /// Validates the update locally, returning any issues found. An empty list means no issues
+    /// were found, but the update may still be rejected by backpack.tf.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        validate_update(&self.id, &self.currencies, &self.details)
+    }
+}
+
+/// Validates the parts of an update.
+pub(crate) fn validate_update<T>(
+    id: &str,
+    currencies: &T,
+    details: &Option<String>,
+) -> Vec<ValidationIssue>
+where
+    T: Serialize,
+{
+    let mut issues = Vec::new();
+    
+    if id.is_empty() {
+        issues.push(ValidationIssue::EmptyListingId);
+    }
+    
+    issues.extend(validate_currencies(currencies));
+    issues.extend(validate_details(details));
+    issues
+}
+
+fn validate_currencies<T>(currencies: &T) -> Vec<ValidationIssue>
+where
+    T: Serialize,
+{
+    let Ok(Value::Object(map)) = serde_json::to_value(currencies) else {
+        return vec![ValidationIssue::InvalidCurrencies];
+    };
+    let mut issues = Vec::new();
+    let mut is_zero = true;
+    
+    for (name, value) in map {
+        let Some(number) = value.as_f64() else {
+            return vec![ValidationIssue::InvalidCurrencies];
+        };
+        
+        if number < 0.0 {
+            issues.push(ValidationIssue::NegativeCurrency {
+                name,
+            });
+        } else if number > 0.0 {
+            is_zero = false;
+        }
+    }
+    
+    if is_zero && issues.is_empty() {
+        issues.push(ValidationIssue::ZeroCurrencies);
+    }
+    
+    issues
+}
+
+fn validate_details(details: &Option<String>) -> Option<ValidationIssue> {
+    let length = details.as_ref()?.chars().count();
+    
+    if length > MAX_DETAILS_LENGTH {
+        Some(ValidationIssue::DetailsTooLong {
+            length,
+            max: MAX_DETAILS_LENGTH,
+        })
+    } else {
+        None
+    }
+}
+
+/// Checks for a paint on an item which can't be painted. Without the item schema this can only
+/// detect attributes which are exclusive to weapons or paint cans.
+fn validate_paint(item: &Item) -> Option<ValidationIssue> {
+    let paint = item.paint?;
+    // 5027-5077 are paint defindexes
+    let reason = if (5027..=5077).contains(&item.defindex) {
+        "a paint can defindex"
+    } else if item.killstreak_tier.is_some() {
+        "a killstreak tier"
+    } else if item.australium {
+        "australium"
+    } else if item.festivized {
+        "festivizer"
+    } else if item.wear.is_some() || item.skin.is_some() {
+        "a skin"
+    } else {
+        return None;
+    };
+    
+    Some(ValidationIssue::Unpaintable {
+        paint,
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tf2_price::{Currencies, ref_to_weps};
+    use tf2_enum::{KillstreakTier, Quality};
+    use serde_json::json;
+    
+    #[test]
+    fn validates_sell_listing() {
+        let listing = CreateListing::Sell {
+            id: 0,
+            currencies: json!({ "keys": -1, "metal": 5 }),
+            details: Some("a".repeat(MAX_DETAILS_LENGTH + 1)),
+            buyout: true,
+            offers: true,
+        };
+        
+        assert_eq!(listing.validate(), vec![
+            ValidationIssue::NegativeCurrency {
+                name: "keys".into(),
+            },
+            ValidationIssue::DetailsTooLong {
+                length: MAX_DETAILS_LENGTH + 1,
+                max: MAX_DETAILS_LENGTH,
+            },
+            ValidationIssue::ZeroItemId,
+        ]);
+    }
+    
+    #[test]
+    fn validates_unpaintable_item() {
+        let listing = CreateListing::Buy {
+            item: Item::new(199, Quality::Strange)
+                .killstreak_tier(KillstreakTier::Killstreak)
+                .paint(Paint::PinkAsHell),
+            currencies: Currencies {
+                keys: 0,
+                weapons: ref_to_weps!(1),
+            },
+            details: None,
+            buyout: true,
+            offers: true,
+        };
+        
+        assert_eq!(listing.validate(), vec![
+            ValidationIssue::Unpaintable {
+                paint: Paint::PinkAsHell,
+                reason: "a killstreak tier",
+            },
+        ]);
+    }
+    
+    #[test]
+    fn valid_update_has_no_issues() {
+        let update = UpdateListing {
+            id: "440_7764221391".into(),
+            currencies: Currencies {
+                keys: 1,
+                weapons: 0,
+            },
+            details: Some("Selling".into()),
+        };
+        
+        assert!(update.validate().is_empty());
+    }
+}
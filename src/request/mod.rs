@@ -3,14 +3,25 @@
 mod listing;
 mod alert;
 mod currencies;
+mod dry_run;
 
 pub use alert::MinMax;
 pub use currencies::ResponseCurrencies;
-pub use listing::{CreateListing, UpdateListing};
+pub use dry_run::DryRunRequest;
+pub use listing::{
+    CreateListing,
+    UpdateListing,
+    ValidationIssue,
+    LostAttribute,
+    DetailsTemplate,
+    DetailsContext,
+};
+pub use listing::validation::MAX_DETAILS_LENGTH;
 pub use listing::create_listing::buy_listing::{
     Item as BuyListingItem,
     ItemParams as BuyListingItemParams,
 };
 
+pub(crate) use listing::validation;
 pub(crate) use listing::create_listing::buy_listing::serializers as listing_serializers;
 pub(crate) mod serializers;
\ No newline at end of file
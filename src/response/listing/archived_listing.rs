@@ -0,0 +1,23 @@
+//! Archived listing.
+
+use super::ListingErrorKind;
+
+/// An error occurred when publishing or deleting an archived listing.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ErrorListing<'a> {
+    /// The error message.
+    pub message: String,
+    /// The ID of the archived listing.
+    pub id: &'a str,
+}
+
+impl ErrorListing<'_> {
+    /// Gets the kind of error parsed from the message.
+    pub fn kind(&self) -> ListingErrorKind {
+        ListingErrorKind::from(self.message.as_str())
+    }
+}
+
+/// The result of publishing or deleting an archived listing. Contains the ID of the archived 
+/// listing on success.
+pub type Result<'a> = std::result::Result<&'a str, ErrorListing<'a>>;
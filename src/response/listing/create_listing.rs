@@ -1,5 +1,6 @@
 //! Create listing.
 
+use super::ListingErrorKind;
 use crate::{request, response};
 
 /// An error occurred when creating a listing.
@@ -11,5 +12,12 @@ pub struct ErrorListing<'a, T> {
     pub query: &'a request::CreateListing<T>,
 }
 
+impl<T> ErrorListing<'_, T> {
+    /// Gets the kind of error parsed from the message.
+    pub fn kind(&self) -> ListingErrorKind {
+        ListingErrorKind::from(self.message.as_str())
+    }
+}
+
 /// The result of creating a listing.
 pub type Result<'a, T> = std::result::Result<response::listing::Listing, ErrorListing<'a, T>>;
\ No newline at end of file
@@ -0,0 +1,136 @@
+//! Listing error kinds.
+
+use crate::error::DRY_RUN_MESSAGE;
+use std::fmt;
+
+/// The kind of error returned for a single listing in a batch request. This is parsed from the
+/// error message in the response, which is free-form text.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ListingErrorKind {
+    /// The item is not in the user's inventory.
+    ItemNotInInventory,
+    /// A listing for the item already exists.
+    DuplicateListing,
+    /// The user has reached their listing limit.
+    ListingLimitReached,
+    /// The listing to update or delete does not exist.
+    ListingNotFound,
+    /// The price of the listing is invalid.
+    InvalidPrice,
+    /// The item is invalid or could not be resolved.
+    InvalidItem,
+    /// The user is banned or otherwise not permitted to list the item.
+    NotPermitted,
+    /// Too many requests were made.
+    RateLimited,
+    /// The request was not sent because the client is in dry-run mode.
+    DryRun,
+    /// Another reason (check the string for more information).
+    Other(String),
+}
+
+impl ListingErrorKind {
+    /// Whether the listing can be retried. Only transient errors such as rate limiting are
+    /// retryable; resubmitting the listing for any other kind of error will fail again.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ListingErrorKind::RateLimited)
+    }
+}
+
+impl From<&str> for ListingErrorKind {
+    fn from(message: &str) -> Self {
+        let lowercase = message.to_lowercase();
+        let contains_any = |patterns: &[&str]| {
+            patterns.iter().any(|pattern| lowercase.contains(pattern))
+        };
+        
+        // The most specific patterns are checked first since messages can match more than one
+        // kind e.g. "Listing limit reached, try again later"
+        if message == DRY_RUN_MESSAGE {
+            ListingErrorKind::DryRun
+        } else if contains_any(&["listing not found"]) {
+            ListingErrorKind::ListingNotFound
+        } else if contains_any(&["not in inventory", "not in your inventory", "not found in your inventory", "you do not own", "you don't own"]) {
+            ListingErrorKind::ItemNotInInventory
+        } else if contains_any(&["already exists", "already listed", "duplicate listing"]) {
+            ListingErrorKind::DuplicateListing
+        } else if contains_any(&["listing limit", "listing cap", "listing slots", "maximum number of listings"]) {
+            ListingErrorKind::ListingLimitReached
+        } else if contains_any(&["banned", "not permitted", "not allowed to"]) {
+            ListingErrorKind::NotPermitted
+        } else if contains_any(&["invalid price", "invalid currencies", "value cannot be zero"]) {
+            ListingErrorKind::InvalidPrice
+        } else if contains_any(&["item is invalid", "invalid item", "unknown item", "cannot be listed"]) {
+            ListingErrorKind::InvalidItem
+        } else if contains_any(&["rate limit", "too many requests", "slow down"]) {
+            ListingErrorKind::RateLimited
+        } else {
+            ListingErrorKind::Other(message.to_owned())
+        }
+    }
+}
+
+impl From<String> for ListingErrorKind {
+    fn from(message: String) -> Self {
+        ListingErrorKind::from(message.as_str())
+    }
+}
+
+impl fmt::Display for ListingErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListingErrorKind::ItemNotInInventory => write!(f, "Item not in inventory"),
+            ListingErrorKind::DuplicateListing => write!(f, "Duplicate listing"),
+            ListingErrorKind::ListingLimitReached => write!(f, "Listing limit reached"),
+            ListingErrorKind::ListingNotFound => write!(f, "Listing not found"),
+            ListingErrorKind::InvalidPrice => write!(f, "Invalid price"),
+            ListingErrorKind::InvalidItem => write!(f, "Invalid item"),
+            ListingErrorKind::NotPermitted => write!(f, "Not permitted"),
+            ListingErrorKind::RateLimited => write!(f, "Rate limited"),
+            ListingErrorKind::DryRun => write!(f, "Dry run"),
+            ListingErrorKind::Other(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_error_kinds() {
+        #[derive(serde::Deserialize)]
+        struct ErrorResult {
+            index: usize,
+            message: String,
+        }
+        
+        #[derive(serde::Deserialize)]
+        struct Response {
+            errors: Vec<ErrorResult>,
+        }
+        
+        // A recorded response from backpack.tf
+        let response: Response = serde_json::from_str(include_str!("../../api/fixtures/update_listings.json")).unwrap();
+        let kinds = response.errors
+            .into_iter()
+            .map(|error| (error.index, ListingErrorKind::from(error.message)))
+            .collect::<Vec<_>>();
+        
+        assert_eq!(kinds, vec![(1, ListingErrorKind::ListingNotFound)]);
+    }
+    
+    #[test]
+    fn unmatched_messages_are_other() {
+        let message = "Quality mismatch on price suggestion";
+        
+        assert_eq!(ListingErrorKind::from(message), ListingErrorKind::Other(message.into()));
+    }
+    
+    #[test]
+    fn only_rate_limited_is_retryable() {
+        assert!(ListingErrorKind::RateLimited.is_retryable());
+        assert!(!ListingErrorKind::ItemNotInInventory.is_retryable());
+        assert!(!ListingErrorKind::Other("sus".into()).is_retryable());
+    }
+}
@@ -0,0 +1,82 @@
+//! App-agnostic listing.
+
+use super::{User, UserAgent, Value};
+use crate::{SteamID, ListingIntent};
+use crate::time::ServerTime;
+use crate::response::currencies::ResponseCurrencies;
+use crate::response::deserializers;
+use chrono::serde::ts_seconds;
+use serde::{Serialize, Deserialize};
+
+/// A listing for any app. Only includes fields which are common to listings for all apps. Use
+/// [`Listing`](super::Listing) for Team Fortress 2 listings.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GenericListing {
+    /// The ID of the listing.
+    pub id: String,
+    /// The SteamID of the listing's user.
+    pub steamid: SteamID,
+    /// The appid of the listing.
+    pub appid: u32,
+    /// The currencies of the listing.
+    pub currencies: ResponseCurrencies,
+    /// The value of the listing.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// The details of the listing.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// The time the listing was listed at.
+    #[serde(with = "ts_seconds")]
+    pub listed_at: ServerTime,
+    /// The time the listing was bumped at.
+    #[serde(with = "ts_seconds")]
+    pub bumped_at: ServerTime,
+    /// The intent of the listing.
+    #[serde(deserialize_with = "deserializers::listing_intent_enum_from_str_or_int")]
+    pub intent: ListingIntent,
+    /// The item of the listing.
+    pub item: GenericItem,
+    /// The count of the listing.
+    #[serde(default)]
+    pub count: u32,
+    /// The user agent of the listing.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<UserAgent>,
+    /// The user of the listing.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+}
+
+impl GenericListing {
+    /// Whether the listing is managed by an agent.
+    pub fn is_automatic(&self) -> bool {
+        self.user_agent.is_some()
+    }
+}
+
+/// An item belonging to a [`GenericListing`].
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GenericItem {
+    /// The appid of the item.
+    pub appid: u32,
+    /// The item's ID. `None` if this listing is a buy order.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserializers::from_optional_number_or_string")]
+    pub id: Option<u64>,
+    /// The item's full name e.g. "Desert Eagle | Blaze (Factory New)".
+    pub name: String,
+    /// The item's market hash name on the Steam Community Market.
+    pub market_name: String,
+    /// The item's image URL.
+    #[serde(default)]
+    #[serde(deserialize_with = "deserializers::default_on_null")]
+    pub image_url: String,
+}
@@ -5,8 +5,11 @@ mod user_agent;
 mod value;
 mod item;
 mod status;
+mod error_kind;
+mod generic;
 
 pub mod attributes;
+pub mod archived_listing;
 pub mod create_listing;
 pub mod update_listing;
 pub use user::{User, Ban};
@@ -14,6 +17,8 @@ pub use item::Item;
 pub use value::Value;
 pub use user_agent::UserAgent;
 pub use status::Status;
+pub use error_kind::ListingErrorKind;
+pub use generic::{GenericListing, GenericItem};
 
 use crate::{SteamID, ListingIntent};
 use crate::time::ServerTime;
@@ -70,6 +75,10 @@ pub struct Listing {
     pub item: Item,
     /// The count of the listing.
     pub count: u32,
+    /// Whether the listing is promoted.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "deserializers::is_false")]
+    pub promoted: bool,
     /// The status of the listing.
     #[serde(default)]
     pub status: Status,
@@ -84,12 +93,13 @@ pub struct Listing {
 }
 
 impl Listing {
-    /// Whether the listing is relistable by checking if the listing was listed after an interval.
+    /// Whether the listing is relistable by checking if the listing was last bumped longer than
+    /// an interval ago.
     pub fn relistable(&self, interval: Duration) -> bool {
         if let Ok(interval) = ChronoDuration::from_std(interval) {
             let cutoff = Utc::now() - interval;
             
-            self.listed_at < cutoff
+            self.bumped_at < cutoff
         } else {
             false
         }
@@ -124,10 +134,18 @@ impl fmt::Display for Listing {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use tf2_enum::{ItemSlot, Spell, Quality};
     
+    /// Gets the listing from `fixtures/listing.json` with the ID replaced.
+    pub(crate) fn get_listing(id: &str) -> Listing {
+        let mut listing: Listing = serde_json::from_str(include_str!("fixtures/listing.json")).unwrap();
+        
+        listing.id = id.into();
+        listing
+    }
+    
     #[test]
     fn parses_listing() {
         let listing: Listing = serde_json::from_str(include_str!("fixtures/listing.json")).unwrap();
@@ -213,5 +231,21 @@ mod tests {
             hat: 1.0
         });
     }
+    
+    #[test]
+    fn parses_generic_listing() {
+        let listing: GenericListing = serde_json::from_str(include_str!("fixtures/websocket_listing_csgo.json")).unwrap();
+        
+        assert_eq!(listing.appid, 730);
+        assert_eq!(listing.intent, ListingIntent::Sell);
+        assert_eq!(listing.currencies, ResponseCurrencies::Cash(600.0));
+        assert_eq!(listing.item.id, Some(28727091699));
+        assert_eq!(listing.item.market_name, "Desert Eagle | Blaze (Factory New)");
+        assert_eq!(u64::from(listing.steamid), 76561197988841702);
+        
+        let listing: GenericListing = serde_json::from_str(include_str!("fixtures/websocket_listing.json")).unwrap();
+        
+        assert_eq!(listing.appid, 440);
+        assert_eq!(listing.item.name, "Disco Beat Down Lord Cockswain's Pith Helmet");
+    }
 }
-
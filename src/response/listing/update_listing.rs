@@ -1,5 +1,6 @@
 //! Update listing.
 
+use super::ListingErrorKind;
 use crate::{SteamID, ListingIntent};
 use crate::request;
 use crate::response::currencies::ResponseCurrencies;
@@ -17,6 +18,13 @@ pub struct ErrorListing<'a, T> {
     pub query: &'a request::UpdateListing<T>,
 }
 
+impl<T> ErrorListing<'_, T> {
+    /// Gets the kind of error parsed from the message.
+    pub fn kind(&self) -> ListingErrorKind {
+        ListingErrorKind::from(self.message.as_str())
+    }
+}
+
 /// A listing was successfully created.
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -55,4 +63,21 @@ pub struct SuccessListing {
 }
 
 /// The result of updating a listing.
-pub type Result<'a, T> = std::result::Result<SuccessListing, ErrorListing<'a, T>>;
\ No newline at end of file
+pub type Result<'a, T> = std::result::Result<SuccessListing, ErrorListing<'a, T>>;
+
+/// Looks up the results of updating listings by listing ID. This is implemented for slices of 
+/// results such as those returned by 
+/// [`update_listings`](crate::BackpackAPI::update_listings).
+pub trait FindByListingId<'a, T> {
+    /// Gets the result for the input listing with the given ID.
+    fn find_by_listing_id(&self, id: &str) -> Option<&Result<'a, T>>;
+}
+
+impl<'a, T> FindByListingId<'a, T> for [Result<'a, T>] {
+    fn find_by_listing_id(&self, id: &str) -> Option<&Result<'a, T>> {
+        self.iter().find(|result| match result {
+            Ok(listing) => listing.id == id,
+            Err(error) => error.query.id == id,
+        })
+    }
+}
\ No newline at end of file
@@ -167,7 +167,7 @@ impl Item {
     /// Gets the paint on the item.
     pub fn get_paint(&self) -> Option<Paint> {
         // 5027-5077 are paint defindexes
-        if self.defindex >= 5027 || self.defindex <= 5077 {
+        if (5027..=5077).contains(&self.defindex) {
             return None;
         }
         
@@ -0,0 +1,405 @@
+//! Backpressure policies for the message channel.
+
+use super::{Event, EventStream, Message};
+use crate::CancellationToken;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// What to do with new events when the receiver isn't keeping up and the channel is full.
+///
+/// Blocking stops the socket from being read until there is room in the channel. If the receiver
+/// falls too far behind, backpack.tf may close the connection. The other policies keep reading
+/// from the socket and drop events instead. Dropped events are counted by the
+/// [`DroppedCounter`] from [`WebsocketBuilder::dropped_counter`](super::WebsocketBuilder::dropped_counter).
+/// Lifecycle messages such as [`Message::Connected`] are never dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backpressure {
+    /// Wait for room in the channel before reading more events.
+    #[default]
+    Block,
+    /// Drop new events while the channel is full.
+    DropNewest,
+    /// Drop the oldest events in the channel to make room for new events.
+    DropOldest,
+    /// Replace a queued listing update or delete with a newer event for the same listing ID, so
+    /// only the latest state of each listing is kept. Other events are queued as normal. If the
+    /// channel is full of distinct listings the oldest event is dropped.
+    CoalesceListings,
+}
+
+/// Counts events dropped by a [`Backpressure`] policy. Events replaced by a newer event for the
+/// same listing are also counted. Clones share the same count.
+#[derive(Debug, Clone, Default)]
+pub struct DroppedCounter(Arc<AtomicU64>);
+
+impl DroppedCounter {
+    /// Creates a new counter starting at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// The number of events dropped.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+    
+    fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The sending half of the message channel, which applies the backpressure policy.
+#[derive(Debug)]
+pub(crate) enum EventSender {
+    /// Waits for room in the channel.
+    Block(mpsc::Sender<Event>),
+    /// Drops the message if the channel is full.
+    DropNewest(mpsc::Sender<Event>, DroppedCounter),
+    /// Queues messages in a buffer which the stream takes messages from.
+    Buffered(BufferedSender),
+}
+
+impl EventSender {
+    /// Sends an event. Lifecycle events are never dropped. Returns `false` if the stream was
+    /// dropped.
+    pub(crate) async fn send(&self, event: Event) -> bool {
+        if event.message.is_lifecycle() {
+            return self.send_lifecycle(event).await;
+        }
+        
+        match self {
+            Self::Block(sender) => sender.send(event).await.is_ok(),
+            Self::DropNewest(sender, dropped) => match sender.try_send(event) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_event)) => {
+                    dropped.increment();
+                    true
+                },
+                Err(TrySendError::Closed(_event)) => false,
+            },
+            Self::Buffered(sender) => sender.push(event),
+        }
+    }
+    
+    /// Sends a lifecycle event, waiting for room in the channel rather than dropping it.
+    /// Returns `false` if the stream was dropped.
+    pub(crate) async fn send_lifecycle(&self, event: Event) -> bool {
+        match self {
+            Self::Block(sender) |
+            Self::DropNewest(sender, _) => sender.send(event).await.is_ok(),
+            Self::Buffered(sender) => sender.push_lifecycle(event),
+        }
+    }
+    
+    /// Completes when the stream is dropped.
+    pub(crate) async fn closed(&self) {
+        match self {
+            Self::Block(sender) |
+            Self::DropNewest(sender, _) => sender.closed().await,
+            Self::Buffered(sender) => sender.shared.receiver_closed.cancelled().await,
+        }
+    }
+}
+
+/// Creates a message channel using the policy. `capacity` is the number of messages which can be
+/// queued before the policy applies.
+pub(crate) fn channel(
+    policy: Backpressure,
+    capacity: usize,
+    dropped: DroppedCounter,
+) -> (EventSender, EventStream) {
+    match policy {
+        Backpressure::Block => {
+            let (sender, receiver) = mpsc::channel(capacity);
+            
+            (EventSender::Block(sender), EventStream::new(receiver))
+        },
+        Backpressure::DropNewest => {
+            let (sender, receiver) = mpsc::channel(capacity);
+            
+            (EventSender::DropNewest(sender, dropped), EventStream::new(receiver))
+        },
+        Backpressure::DropOldest |
+        Backpressure::CoalesceListings => {
+            // The stream takes messages straight from the buffer, so every queued message can be
+            // dropped or replaced until it is received
+            let shared = Arc::new(Shared {
+                buffer: Mutex::new(Buffer {
+                    queue: VecDeque::new(),
+                    waker: None,
+                    sender_dropped: false,
+                }),
+                receiver_closed: CancellationToken::new(),
+                capacity: capacity.max(1),
+                coalesce: policy == Backpressure::CoalesceListings,
+                dropped,
+            });
+            let sender = BufferedSender {
+                shared: Arc::clone(&shared),
+            };
+            let receiver = BufferedReceiver {
+                shared,
+            };
+            
+            (EventSender::Buffered(sender), EventStream::buffered(receiver))
+        },
+    }
+}
+
+/// Sends messages into a buffer which the stream takes messages from.
+#[derive(Debug)]
+pub(crate) struct BufferedSender {
+    shared: Arc<Shared>,
+}
+
+impl BufferedSender {
+    fn push(&self, event: Event) -> bool {
+        let shared = &self.shared;
+        
+        if shared.receiver_closed.is_cancelled() {
+            return false;
+        }
+        
+        let mut buffer = shared.buffer.lock().unwrap();
+        let queue = &mut buffer.queue;
+        let replace = if shared.coalesce {
+            listing_id(&event.message).and_then(|id| {
+                queue.iter().position(|queued| listing_id(&queued.message) == Some(id))
+            })
+        } else {
+            None
+        };
+        
+        if let Some(index) = replace {
+            queue[index] = event;
+            shared.dropped.increment();
+            return true;
+        }
+        
+        if queue.len() >= shared.capacity {
+            shared.dropped.increment();
+            
+            // Lifecycle messages are never dropped, so the oldest other message makes room. If
+            // the buffer only holds lifecycle messages the new message is dropped instead.
+            match queue.iter().position(|queued| !queued.message.is_lifecycle()) {
+                Some(index) => {
+                    queue.remove(index);
+                },
+                None => return true,
+            }
+        }
+        
+        buffer.push(event);
+        true
+    }
+    
+    /// Queues a lifecycle event without dropping or replacing other events.
+    fn push_lifecycle(&self, event: Event) -> bool {
+        if self.shared.receiver_closed.is_cancelled() {
+            return false;
+        }
+        
+        self.shared.buffer.lock().unwrap().push(event);
+        true
+    }
+}
+
+impl Drop for BufferedSender {
+    fn drop(&mut self) {
+        let mut buffer = self.shared.buffer.lock().unwrap();
+        
+        buffer.sender_dropped = true;
+        buffer.wake();
+    }
+}
+
+/// Takes messages from the buffer of a [`BufferedSender`].
+#[derive(Debug)]
+pub(crate) struct BufferedReceiver {
+    shared: Arc<Shared>,
+}
+
+impl BufferedReceiver {
+    /// Takes the next message from the buffer. Returns `None` once the buffer is empty and either
+    /// half was dropped or closed.
+    pub(crate) fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        let mut buffer = self.shared.buffer.lock().unwrap();
+        
+        if let Some(event) = buffer.queue.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        
+        if buffer.sender_dropped || self.shared.receiver_closed.is_cancelled() {
+            return Poll::Ready(None);
+        }
+        
+        buffer.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+    
+    /// Stops the sender from queueing more messages.
+    pub(crate) fn close(&mut self) {
+        self.shared.receiver_closed.cancel();
+    }
+}
+
+impl Drop for BufferedReceiver {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// State shared between a [`BufferedSender`] and its [`BufferedReceiver`].
+#[derive(Debug)]
+struct Shared {
+    buffer: Mutex<Buffer>,
+    /// Cancelled when the receiver is dropped or closed.
+    receiver_closed: CancellationToken,
+    capacity: usize,
+    coalesce: bool,
+    dropped: DroppedCounter,
+}
+
+#[derive(Debug)]
+struct Buffer {
+    queue: VecDeque<Event>,
+    /// The waker of the receiver waiting for a message.
+    waker: Option<Waker>,
+    /// Whether the sender was dropped.
+    sender_dropped: bool,
+}
+
+impl Buffer {
+    /// Queues a message and wakes the receiver.
+    fn push(&mut self, event: Event) {
+        self.queue.push_back(event);
+        self.wake();
+    }
+                
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Gets the listing ID of a listing update or delete.
+fn listing_id(message: &Message) -> Option<&str> {
+    match message {
+        Message::ListingUpdate(listing) |
+        Message::ListingDelete(listing) => Some(listing.id.as_str()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::listing::Listing;
+    use std::time::Duration;
+    
+    fn update(listing_id: &str, event_id: &str) -> Event {
+        let mut listing: Listing = serde_json::from_str(include_str!("../response/listing/fixtures/websocket_listing.json")).unwrap();
+        
+        listing.id = listing_id.into();
+        event(event_id, Message::ListingUpdate(listing))
+    }
+    
+    fn event(id: &str, message: Message) -> Event {
+        Event {
+            id: id.into(),
+            ..Event::lifecycle(message)
+        }
+    }
+    
+    async fn receive_ids(mut stream: EventStream) -> Vec<String> {
+        let mut ids = Vec::new();
+        
+        while let Some(event) = stream.recv().await {
+            ids.push(event.id);
+        }
+        
+        ids
+    }
+    
+    #[tokio::test]
+    async fn drops_newest_and_oldest() {
+        let dropped = DroppedCounter::new();
+        let (sender, stream) = channel(Backpressure::DropNewest, 2, dropped.clone());
+        
+        for id in ["1", "2", "3"] {
+            assert!(sender.send(update("440_1", id)).await);
+        }
+        
+        drop(sender);
+        assert_eq!(receive_ids(stream).await, vec!["1", "2"]);
+        assert_eq!(dropped.get(), 1);
+        
+        let dropped = DroppedCounter::new();
+        let (sender, stream) = channel(Backpressure::DropOldest, 2, dropped.clone());
+        
+        for id in ["1", "2", "3", "4"] {
+            assert!(sender.send(update("440_1", id)).await);
+        }
+        
+        drop(sender);
+        assert_eq!(receive_ids(stream).await, vec!["3", "4"]);
+        assert_eq!(dropped.get(), 2);
+    }
+        
+    #[tokio::test]
+    async fn never_drops_lifecycle_messages() {
+        let dropped = DroppedCounter::new();
+        let (sender, stream) = channel(Backpressure::DropNewest, 1, dropped.clone());
+        // The lifecycle message waits for room in the channel
+        let sending = tokio::spawn(async move {
+            for event in [update("440_1", "1"), update("440_2", "2"), event("connected", Message::Connected)] {
+                assert!(sender.send(event).await);
+            }
+        });
+        
+        assert_eq!(receive_ids(stream).await, vec!["1", "connected"]);
+        assert_eq!(dropped.get(), 1);
+        sending.await.unwrap();
+        
+        for policy in [Backpressure::DropOldest, Backpressure::CoalesceListings] {
+            let dropped = DroppedCounter::new();
+            let (sender, stream) = channel(policy, 2, dropped.clone());
+            let events = [
+                event("connected", Message::Connected),
+                update("440_1", "1"),
+                update("440_2", "2"),
+                update("440_3", "3"),
+                event("stalled", Message::Stalled(Duration::ZERO)),
+                update("440_4", "4"),
+            ];
+            
+            for event in events {
+                assert!(sender.send(event).await);
+            }
+            
+            drop(sender);
+            assert_eq!(receive_ids(stream).await, vec!["connected", "stalled", "4"]);
+            assert_eq!(dropped.get(), 3);
+        }
+    }
+    
+    #[tokio::test]
+    async fn coalesces_listings() {
+        let dropped = DroppedCounter::new();
+        let (sender, stream) = channel(Backpressure::CoalesceListings, 10, dropped.clone());
+        
+        assert!(sender.send(event("first", Message::Connected)).await);
+        assert!(sender.send(update("440_1", "a")).await);
+        assert!(sender.send(update("440_2", "b")).await);
+        assert!(sender.send(update("440_1", "c")).await);
+        
+        drop(sender);
+        assert_eq!(receive_ids(stream).await, vec!["first", "c", "b"]);
+        assert_eq!(dropped.get(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+//! Builder for configuring websocket connections.
+
+use super::{Backpressure, DroppedCounter, Error, EventStream, Filter, HealthOptions, HealthStats, Recorder, ReconnectOptions, CONNECT_ADDR};
+use super::backpressure::{self, EventSender};
+use super::handlers::read_events;
+use super::reconnect;
+use crate::CancellationToken;
+use std::fmt;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::{self, HeaderMap, HeaderName, HeaderValue};
+
+/// The default capacity of the message channel.
+const DEFAULT_CHANNEL_CAPACITY: usize = 100;
+
+/// Builder for connecting to the websocket.
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::websocket::{Filter, WebsocketBuilder};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut websocket = WebsocketBuilder::new()
+///         .header("User-Agent", "my-bot/1.0")
+///         .channel_capacity(1000)
+///         .filter(Filter::new().defindex(5021))
+///         .connect()
+///         .await?;
+///
+///     while let Some(event) = websocket.recv().await {
+///         println!("{}", event.message);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct WebsocketBuilder {
+    url: String,
+    headers: Result<HeaderMap, http::Error>,
+    channel_capacity: usize,
+    backpressure: Backpressure,
+    dropped: DroppedCounter,
+    connector: Option<Connector>,
+    filter: Option<Filter>,
+    recorder: Option<Recorder>,
+    health: Option<HealthOptions>,
+    stats: HealthStats,
+}
+
+impl Default for WebsocketBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebsocketBuilder {
+    /// Creates a new builder.
+    pub fn new() -> Self {
+        Self {
+            url: CONNECT_ADDR.into(),
+            headers: Ok(HeaderMap::new()),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            backpressure: Backpressure::default(),
+            dropped: DroppedCounter::new(),
+            connector: None,
+            filter: None,
+            recorder: None,
+            health: None,
+            stats: HealthStats::new(),
+        }
+    }
+    
+    /// Sets the URL to connect to. Defaults to `wss://ws.backpack.tf/events`.
+    pub fn url<S>(mut self, url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.url = url.into();
+        self
+    }
+    
+    /// Adds a header to the connection request such as `User-Agent` or `Authorization`. An
+    /// invalid name or value is returned as an error when connecting.
+    pub fn header<K, V>(mut self, name: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.headers = self.headers.and_then(|mut headers| {
+            let name = HeaderName::try_from(name).map_err(Into::into)?;
+            let value = HeaderValue::try_from(value).map_err(Into::into)?;
+            
+            headers.append(name, value);
+            Ok(headers)
+        });
+        self
+    }
+    
+    /// Sets the capacity of the message channel. The capacity is at least 1. Defaults to 100.
+    pub fn channel_capacity(mut self, channel_capacity: usize) -> Self {
+        self.channel_capacity = channel_capacity.max(1);
+        self
+    }
+    
+    /// Sets what to do with new events when the channel is full. Defaults to
+    /// [`Backpressure::Block`].
+    pub fn backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+    
+    /// Gets a counter of events dropped by the [`Backpressure`] policy for connections made by
+    /// this builder.
+    pub fn dropped_counter(&self) -> DroppedCounter {
+        self.dropped.clone()
+    }
+    
+    /// Sets the connector used to establish TLS connections. By default the connector is chosen
+    /// by the enabled TLS feature.
+    pub fn connector(mut self, connector: Connector) -> Self {
+        self.connector = Some(connector);
+        self
+    }
+    
+    /// Only sends listings which match the filter. See [`Filter`].
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+    
+    /// Records every text frame received to the recorder. Frames are recorded before they are
+    /// filtered. See [`Recorder`].
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+    
+    /// Monitors the health of the connection, pinging the server and detecting stalls. See
+    /// [`HealthOptions`].
+    pub fn health(mut self, health: HealthOptions) -> Self {
+        self.health = Some(health);
+        self
+    }
+    
+    /// Gets a handle to the health statistics for connections made by this builder. Statistics
+    /// other than the ping round-trip time are tracked even if [`health`](Self::health) is not
+    /// set.
+    pub fn health_stats(&self) -> HealthStats {
+        self.stats.clone()
+    }
+    
+    /// Connects to the websocket.
+    ///
+    /// Dropping the stream closes the connection.
+    pub async fn connect(self) -> Result<EventStream, Error> {
+        let (sender, events) = self.channel();
+        let connection = self.into_connection()?;
+        let stream = connection.open().await?;
+        
+        tokio::spawn(async move {
+            read_events(stream, &sender, &connection).await;
+        });
+        
+        Ok(events)
+    }
+    
+    /// Connects to the websocket, reconnecting whenever the connection is lost. See
+    /// [`connect_with_reconnect`](super::connect_with_reconnect). Returns an error only if the
+    /// connection request is invalid.
+    #[allow(clippy::result_large_err)]
+    pub fn connect_with_reconnect(
+        self,
+        options: ReconnectOptions,
+        cancel: CancellationToken,
+    ) -> Result<EventStream, Error> {
+        let (sender, events) = self.channel();
+        let connection = self.into_connection()?;
+        
+        tokio::spawn(reconnect::run(connection, options, sender, cancel));
+        
+        Ok(events)
+    }
+    
+    fn channel(&self) -> (EventSender, EventStream) {
+        backpressure::channel(self.backpressure, self.channel_capacity, self.dropped.clone())
+    }
+    
+    #[allow(clippy::result_large_err)]
+    fn into_connection(self) -> Result<Connection, Error> {
+        let mut request = self.url.into_client_request()?;
+        
+        request.headers_mut().extend(self.headers?);
+        
+        Ok(Connection {
+            request,
+            connector: self.connector,
+            filter: self.filter,
+            recorder: self.recorder,
+            health: self.health,
+            stats: self.stats,
+        })
+    }
+}
+
+impl fmt::Debug for WebsocketBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebsocketBuilder")
+            .field("url", &self.url)
+            .field("headers", &self.headers)
+            .field("channel_capacity", &self.channel_capacity)
+            .field("backpressure", &self.backpressure)
+            .field("dropped", &self.dropped)
+            .field("connector", &self.connector.is_some())
+            .field("filter", &self.filter)
+            .field("recorder", &self.recorder)
+            .field("health", &self.health)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+/// The configuration for opening a connection.
+pub(crate) struct Connection {
+    request: Request,
+    connector: Option<Connector>,
+    pub(crate) filter: Option<Filter>,
+    pub(crate) recorder: Option<Recorder>,
+    pub(crate) health: Option<HealthOptions>,
+    pub(crate) stats: HealthStats,
+}
+
+impl Connection {
+    /// Opens a connection.
+    pub(crate) async fn open(&self) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, Error> {
+        let request = self.request.clone();
+        
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls-native-roots", feature = "rustls-tls-webpki-roots"))]
+        let (stream, _response) = tokio_tungstenite::connect_async_tls_with_config(
+            request,
+            None,
+            false,
+            self.connector.clone(),
+        ).await?;
+        // Without a TLS feature only plain connections are supported so the connector is unused
+        #[cfg(not(any(feature = "native-tls", feature = "rustls-tls-native-roots", feature = "rustls-tls-webpki-roots")))]
+        let (stream, _response) = {
+            let _ = &self.connector;
+            
+            tokio_tungstenite::connect_async_with_config(
+                request,
+                None,
+                false,
+            ).await?
+        };
+        
+        Ok(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::{Event, Message, StallAction};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request as ServerRequest, Response};
+    use futures_util::{SinkExt, StreamExt};
+    
+    #[test]
+    fn invalid_header_is_error() {
+        let builder = WebsocketBuilder::new().header("User Agent", "bot");
+        
+        assert!(matches!(builder.into_connection(), Err(Error::HttpFormat(_))));
+    }
+    
+    #[tokio::test]
+    async fn clamps_zero_channel_capacity() {
+        let builder = WebsocketBuilder::new().channel_capacity(0);
+        
+        assert_eq!(builder.channel_capacity, 1);
+        // A channel with a capacity of 0 panics
+        let _channel = builder.channel();
+    }
+    
+    #[tokio::test]
+    async fn connects_to_local_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut user_agent = None;
+            // The callback's error type is defined by tungstenite
+            #[allow(clippy::result_large_err)]
+            let mut websocket = tokio_tungstenite::accept_hdr_async(stream, |request: &ServerRequest, response: Response| {
+                user_agent = request.headers().get("User-Agent").cloned();
+                Ok(response)
+            }).await.unwrap();
+            
+            websocket.send(WsMessage::text(r#"[{"id": "1", "event": "client-limit-exceeded", "payload": {"message": "hi"}}]"#)).await.unwrap();
+            websocket.close(None).await.unwrap();
+            user_agent
+        });
+        let mut stream = WebsocketBuilder::new()
+            .url(format!("ws://{addr}"))
+            .header("User-Agent", "test-agent")
+            .channel_capacity(1)
+            .connect()
+            .await
+            .unwrap();
+        
+        assert!(matches!(stream.recv().await, Some(Event { id, message: Message::ClientLimitExceeded(message), .. }) if id == "1" && message == "hi"));
+        assert!(stream.recv().await.is_none());
+        assert_eq!(server.await.unwrap().unwrap(), "test-agent");
+    }
+    
+    #[tokio::test]
+    async fn reports_stalls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut websocket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            
+            // Read without sending events so pings are answered
+            while let Some(Ok(_message)) = websocket.next().await {}
+        });
+        
+        let builder = WebsocketBuilder::new()
+            .url(format!("ws://{addr}"))
+            .health(HealthOptions {
+                stall_threshold: Duration::from_millis(100),
+                on_stall: StallAction::Notify,
+                ping_interval: Some(Duration::from_millis(10)),
+            });
+        let stats = builder.health_stats();
+        let mut stream = builder.connect().await.unwrap();
+        
+        assert!(matches!(stream.recv().await, Some(Event { message: Message::Stalled(duration), .. }) if duration >= Duration::from_millis(100)));
+        assert!(stats.ping_rtt().is_some());
+        assert!(stats.since_last_event().is_none());
+    }
+}
@@ -0,0 +1,177 @@
+//! Events and the stream of events from the websocket.
+
+use super::Message;
+use super::backpressure::BufferedReceiver;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
+use tokio::sync::mpsc;
+
+/// An event from the websocket.
+#[derive(Debug)]
+pub struct Event {
+    /// The event ID. Lifecycle messages such as [`Message::Connected`] have an empty ID.
+    pub id: String,
+    /// The message.
+    pub message: Message,
+    /// When the frame containing the event was received. For replays this is when the frame was
+    /// recorded.
+    pub received_at: DateTime<Utc>,
+    /// The position of the event among the events sent from the same frame, starting at 0.
+    pub batch_index: usize,
+    /// The number of events sent from the same frame.
+    pub batch_len: usize,
+}
+
+impl Event {
+    /// Creates a lifecycle event which isn't part of a frame.
+    pub(crate) fn lifecycle(message: Message) -> Self {
+        Self {
+            id: String::new(),
+            message,
+            received_at: Utc::now(),
+            batch_index: 0,
+            batch_len: 1,
+        }
+    }
+    
+    /// Creates the events for a batch of messages received in one frame.
+    pub(crate) fn batch(
+        messages: Vec<(String, Message)>,
+        received_at: DateTime<Utc>,
+    ) -> impl Iterator<Item = Self> {
+        let batch_len = messages.len();
+        
+        messages
+            .into_iter()
+            .enumerate()
+            .map(move |(batch_index, (id, message))| Self {
+                id,
+                message,
+                received_at,
+                batch_index,
+                batch_len,
+            })
+    }
+}
+
+/// A stream of events from the websocket. Implements [`Stream`] so it can be used with stream
+/// combinators, or events can be read one at a time with [`recv`](EventStream::recv).
+///
+/// The stream ends when the connection closes. Dropping the stream closes the connection.
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::websocket::{Message, connect};
+/// use futures_util::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut updates = connect()
+///         .await?
+///         .filter_map(|event| async move {
+///             match event.message {
+///                 Message::ListingUpdate(listing) => Some(listing),
+///                 _ => None,
+///             }
+///         })
+///         .boxed();
+///
+///     while let Some(listing) = updates.next().await {
+///         println!("{}", listing.item.name);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EventStream {
+    receiver: Receiver,
+}
+
+/// Where an [`EventStream`] receives its events from.
+#[derive(Debug)]
+enum Receiver {
+    /// A channel.
+    Channel(mpsc::Receiver<Event>),
+    /// A buffer where queued events can still be dropped or replaced.
+    Buffered(BufferedReceiver),
+}
+
+impl EventStream {
+    pub(crate) fn new(receiver: mpsc::Receiver<Event>) -> Self {
+        Self {
+            receiver: Receiver::Channel(receiver),
+        }
+    }
+    
+    pub(crate) fn buffered(receiver: BufferedReceiver) -> Self {
+        Self {
+            receiver: Receiver::Buffered(receiver),
+        }
+    }
+    
+    /// Receives the next event. Returns `None` once the stream has ended.
+    pub async fn recv(&mut self) -> Option<Event> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+    
+    /// Closes the stream, stopping the connection. Events already received can still be read.
+    pub fn close(&mut self) {
+        match &mut self.receiver {
+            Receiver::Channel(receiver) => receiver.close(),
+            Receiver::Buffered(receiver) => receiver.close(),
+        }
+    }
+    
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        match &mut self.receiver {
+            Receiver::Channel(receiver) => receiver.poll_recv(cx),
+            Receiver::Buffered(receiver) => receiver.poll_recv(cx),
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Event;
+    
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    
+    #[tokio::test]
+    async fn streams_batch_positions() {
+        let (sender, receiver) = mpsc::channel(10);
+        let received_at = Utc::now();
+        let messages = vec![
+            ("1".to_string(), Message::Connected),
+            ("2".to_string(), Message::Reconnecting(1)),
+        ];
+        
+        for event in Event::batch(messages, received_at) {
+            sender.send(event).await.unwrap();
+        }
+        
+        drop(sender);
+        
+        let positions = EventStream::new(receiver)
+            .map(|event| (event.id, event.batch_index, event.batch_len, event.received_at))
+            .collect::<Vec<_>>()
+            .await;
+        
+        assert_eq!(positions, vec![
+            ("1".to_string(), 0, 2, received_at),
+            ("2".to_string(), 1, 2, received_at),
+        ]);
+    }
+}
@@ -0,0 +1,270 @@
+//! Filtering websocket listings before they are parsed.
+
+use crate::{ListingIntent, SteamID};
+use crate::response::deserializers;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use serde_json::value::RawValue;
+use tf2_enum::Quality;
+
+/// The fields of a listing which are read before a listing is fully parsed. Fields other than
+/// the appid are `None` if they're missing or can't be read, which is common for listings from
+/// other apps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListingSummary {
+    /// The appid of the listing.
+    pub appid: u32,
+    /// The intent of the listing.
+    pub intent: Option<ListingIntent>,
+    /// The SteamID of the listing's user.
+    pub steamid: Option<SteamID>,
+    /// The item's defindex.
+    pub defindex: Option<i32>,
+    /// The item's quality.
+    pub quality: Option<Quality>,
+    /// The item's full name.
+    pub item_name: Option<String>,
+    /// The ID of the item's particle effect.
+    pub particle: Option<u32>,
+    /// Whether the listing is managed by an agent.
+    pub is_automatic: bool,
+}
+
+impl ListingSummary {
+    /// Reads the summary from a listing payload. Returns `None` if the payload isn't an object
+    /// with an appid.
+    pub fn from_payload(payload: &RawValue) -> Option<Self> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RawListing {
+            appid: u32,
+            #[serde(default)]
+            #[serde(deserialize_with = "optional_intent")]
+            intent: Option<ListingIntent>,
+            #[serde(default)]
+            steamid: Option<SteamID>,
+            #[serde(default)]
+            item: Option<RawItem>,
+            #[serde(default)]
+            user_agent: Option<IgnoredAny>,
+        }
+        
+        #[derive(Deserialize)]
+        struct RawItem {
+            #[serde(default)]
+            defindex: Option<i32>,
+            #[serde(default)]
+            quality: Option<RawId>,
+            #[serde(default)]
+            name: Option<String>,
+            #[serde(default)]
+            particle: Option<RawId>,
+        }
+        
+        #[derive(Deserialize)]
+        struct RawId {
+            id: u32,
+        }
+        
+        fn optional_intent<'de, D>(deserializer: D) -> Result<Option<ListingIntent>, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            deserializers::listing_intent_enum_from_str_or_int(deserializer).map(Some)
+        }
+        
+        let listing = serde_json::from_str::<RawListing>(payload.get()).ok()?;
+        let item = listing.item;
+        
+        Some(Self {
+            appid: listing.appid,
+            intent: listing.intent,
+            steamid: listing.steamid,
+            defindex: item.as_ref().and_then(|item| item.defindex),
+            quality: item.as_ref()
+                .and_then(|item| item.quality.as_ref())
+                .and_then(|quality| Quality::try_from(quality.id).ok()),
+            item_name: item.as_ref().and_then(|item| item.name.clone()),
+            particle: item.as_ref()
+                .and_then(|item| item.particle.as_ref())
+                .map(|particle| particle.id),
+            is_automatic: listing.user_agent.is_some(),
+        })
+    }
+}
+
+/// A custom filter predicate.
+type Predicate = Arc<dyn Fn(&ListingSummary) -> bool + Send + Sync>;
+
+/// A filter for listing events, applied before listings are fully parsed and sent to the
+/// receiver. Listings which don't match are dropped.
+///
+/// Each criterion matches any of the values given for it, and a listing must match every
+/// criterion which is set. A filter with no criteria matches every listing. Events other than
+/// listing updates and deletes always pass.
+///
+/// # Examples
+/// ```
+/// use backpacktf_api::ListingIntent;
+/// use backpacktf_api::websocket::Filter;
+/// use backpacktf_api::tf2_enum::Quality;
+///
+/// // Unusual Team Captains and Killer Exclusives from bots
+/// let filter = Filter::new()
+///     .intent(ListingIntent::Sell)
+///     .quality(Quality::Unusual)
+///     .defindex(378)
+///     .defindex(295)
+///     .is_automatic(true);
+/// ```
+#[derive(Clone, Default)]
+pub struct Filter {
+    appids: HashSet<u32>,
+    intent: Option<ListingIntent>,
+    steamids: HashSet<SteamID>,
+    defindexes: HashSet<i32>,
+    qualities: HashSet<Quality>,
+    item_names: HashSet<String>,
+    particles: HashSet<u32>,
+    is_automatic: Option<bool>,
+    predicates: Vec<Predicate>,
+}
+
+impl Filter {
+    /// Creates a new filter which matches every listing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Matches listings for the appid.
+    pub fn appid(mut self, appid: u32) -> Self {
+        self.appids.insert(appid);
+        self
+    }
+    
+    /// Matches listings with the intent.
+    pub fn intent(mut self, intent: ListingIntent) -> Self {
+        self.intent = Some(intent);
+        self
+    }
+    
+    /// Matches listings from the user.
+    pub fn steamid(mut self, steamid: SteamID) -> Self {
+        self.steamids.insert(steamid);
+        self
+    }
+    
+    /// Matches listings for items with the defindex.
+    pub fn defindex(mut self, defindex: i32) -> Self {
+        self.defindexes.insert(defindex);
+        self
+    }
+    
+    /// Matches listings for items with the quality.
+    pub fn quality(mut self, quality: Quality) -> Self {
+        self.qualities.insert(quality);
+        self
+    }
+    
+    /// Matches listings for items with the full name e.g. "Strange Professional Killstreak Pain
+    /// Train".
+    pub fn item_name<S>(mut self, item_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.item_names.insert(item_name.into());
+        self
+    }
+    
+    /// Matches listings for items with the particle effect ID.
+    pub fn particle(mut self, particle: u32) -> Self {
+        self.particles.insert(particle);
+        self
+    }
+    
+    /// Matches listings which are or are not managed by an agent.
+    pub fn is_automatic(mut self, is_automatic: bool) -> Self {
+        self.is_automatic = Some(is_automatic);
+        self
+    }
+    
+    /// Matches listings for which the predicate returns `true`.
+    pub fn predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&ListingSummary) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+    
+    /// Whether the listing matches the filter.
+    pub fn matches(&self, listing: &ListingSummary) -> bool {
+        fn any_of<T>(set: &HashSet<T>, value: Option<&T>) -> bool
+        where
+            T: Eq + std::hash::Hash,
+        {
+            set.is_empty() || value.is_some_and(|value| set.contains(value))
+        }
+        
+        any_of(&self.appids, Some(&listing.appid))
+            && self.intent.is_none_or(|intent| listing.intent == Some(intent))
+            && any_of(&self.steamids, listing.steamid.as_ref())
+            && any_of(&self.defindexes, listing.defindex.as_ref())
+            && any_of(&self.qualities, listing.quality.as_ref())
+            && any_of(&self.item_names, listing.item_name.as_ref())
+            && any_of(&self.particles, listing.particle.as_ref())
+            && self.is_automatic.is_none_or(|is_automatic| listing.is_automatic == is_automatic)
+            && self.predicates.iter().all(|predicate| predicate(listing))
+    }
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter")
+            .field("appids", &self.appids)
+            .field("intent", &self.intent)
+            .field("steamids", &self.steamids)
+            .field("defindexes", &self.defindexes)
+            .field("qualities", &self.qualities)
+            .field("item_names", &self.item_names)
+            .field("particles", &self.particles)
+            .field("is_automatic", &self.is_automatic)
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn get_payload() -> Box<RawValue> {
+        serde_json::from_str(include_str!("../response/listing/fixtures/websocket_listing.json")).unwrap()
+    }
+    
+    #[test]
+    fn summarizes_listing() {
+        let summary = ListingSummary::from_payload(&get_payload()).unwrap();
+        
+        assert_eq!(summary.appid, 440);
+        assert_eq!(summary.intent, Some(ListingIntent::Sell));
+        assert_eq!(summary.defindex, Some(439));
+        assert_eq!(summary.quality, Some(Quality::Unusual));
+        assert_eq!(summary.particle, Some(62));
+        assert!(!summary.is_automatic);
+    }
+    
+    #[test]
+    fn matches_criteria() {
+        let summary = ListingSummary::from_payload(&get_payload()).unwrap();
+        
+        assert!(Filter::new().matches(&summary));
+        assert!(Filter::new().defindex(1).defindex(439).quality(Quality::Unusual).matches(&summary));
+        assert!(!Filter::new().intent(ListingIntent::Buy).matches(&summary));
+        assert!(!Filter::new().is_automatic(true).matches(&summary));
+        assert!(!Filter::new().predicate(|listing| listing.particle == Some(13)).matches(&summary));
+    }
+}
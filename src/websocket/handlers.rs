@@ -1,45 +1,28 @@
 //! Handlers for reading messages.
 
-use super::Message;
-use crate::response::listing::Listing;
-use tokio::sync::mpsc::error::SendError;
-use tokio::sync::mpsc;
+use super::{Event, Filter, ListingSummary, Message, StallAction};
+use super::backpressure::EventSender;
+use super::builder::Connection;
+use super::health::{HealthAction, Monitor};
+use crate::response::listing::{GenericListing, Listing};
+use std::fmt;
+use std::pin::pin;
+use std::time::Duration;
+use chrono::Utc;
 use tokio::net::TcpStream;
 use tokio_tungstenite::{WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::tungstenite;
 use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
-use futures_util::StreamExt;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use futures_util::{SinkExt, StreamExt};
+use futures_util::future::{select, Either};
 use serde::Deserialize;
 use serde_json::value::RawValue;
 
 const APPID_TEAM_FORTRESS_2: u32 = 440;
-
-/// An error from an event.
-#[derive(thiserror::Error, Debug)]
-enum EventError<'a> {
-    /// An error was encountered sending a message.
-    #[error("{}", .0)]
-    Send(#[from] SendError<(String, Message)>),
-    /// An error was encountered deserializing a message.
-    #[error("{}", .0)]
-    Serde(serde_json::Error, &'a RawValue),
-    /// This should be unreachable.
-    #[error("Unreachable code reached")]
-    Unreachable,
-}
-
-/// The type of event from the websocket.
-#[derive(Deserialize, Debug)]
-enum EventType {
-    /// A listing was updated.
-    #[serde(rename = "listing-update")]
-    ListingUpdate,
-    /// A listing was deleted.
-    #[serde(rename = "listing-delete")]
-    ListingDelete,
-    /// Client limit was exceeded.
-    #[serde(rename = "client-limit-exceeded")]
-    ClientLimitExceeded,
-}
+const EVENT_LISTING_UPDATE: &str = "listing-update";
+const EVENT_LISTING_DELETE: &str = "listing-delete";
+const EVENT_CLIENT_LIMIT_EXCEEDED: &str = "client-limit-exceeded";
 
 /// An event from the websocket.
 #[derive(Deserialize, Debug)]
@@ -47,14 +30,13 @@ struct EventMessage<'a> {
     /// The event ID.
     id: String,
     /// The type of event.
-    event: EventType,
+    event: String,
     /// The payload of the event.
     #[serde(borrow)]
     payload: &'a RawValue,
 }
 
-/// A listing from another app other than Team Fortress 2. This reads only the appid from the 
-/// payload.
+/// Reads only the appid from a listing payload.
 #[derive(Debug, Deserialize)]
 struct AppType {
     appid: u32,
@@ -66,89 +48,191 @@ struct StringMessage {
     message: String,
 }
 
-/// Intermediary for converting raw messages into [`Message`].
-struct Event {
-    id: String,
-    message: Message,
+impl EventMessage<'_> {
+    /// Parses the payload into a message. `appid` is the appid read from a listing payload.
+    fn into_message(self, appid: Option<u32>) -> (String, Message) {
+        let payload = self.payload;
+        let parsed = match self.event.as_str() {
+            EVENT_LISTING_UPDATE => parse_listing(payload, appid)
+                .map(|listing| listing.map_or_else(
+                    |(appid, listing, payload)| Message::ListingUpdateOtherApp {
+                        appid,
+                        listing,
+                        payload,
+                    },
+                    Message::ListingUpdate,
+                )),
+            EVENT_LISTING_DELETE => parse_listing(payload, appid)
+                .map(|listing| listing.map_or_else(
+                    |(appid, listing, payload)| Message::ListingDeleteOtherApp {
+                        appid,
+                        listing,
+                        payload,
+                    },
+                    Message::ListingDelete,
+                )),
+            EVENT_CLIENT_LIMIT_EXCEEDED => {
+                let message = serde_json::from_str::<StringMessage>(payload.get())
+                    .map(|string_message| string_message.message)
+                    .unwrap_or_else(|_| payload.get().to_owned());
+                
+                Ok(Message::ClientLimitExceeded(message))
+            },
+            _ => Ok(Message::Unknown {
+                event: self.event.clone(),
+                payload: payload.to_owned(),
+            }),
+        };
+        let message = parsed.unwrap_or_else(|error| {
+            log::debug!("Error deserializing event payload: {error}\n\n{payload}");
+            
+            Message::ParseError {
+                event: Some(self.event),
+                error: error.to_string(),
+                payload: payload.to_owned(),
+            }
+        });
+        
+        (self.id, message)
+    }
 }
 
-impl<'a> TryFrom<EventMessage<'a>> for Event {
-    type Error = EventError<'a>;
+/// The appid, parsed listing and raw payload of a listing from an app other than Team Fortress 2.
+type OtherAppListing = (u32, Option<Box<GenericListing>>, Box<RawValue>);
     
-    fn try_from(message: EventMessage<'a>) -> Result<Self, Self::Error> {
-        use EventType::*;
+/// Parses a listing payload into the model for its appid. Listings from other apps are returned
+/// with their appid and raw payload, along with the listing parsed as a [`GenericListing`] if it
+/// could be parsed. Payloads without an appid are parsed as a Team Fortress 2 listing.
+fn parse_listing(
+    payload: &RawValue,
+    appid: Option<u32>,
+) -> Result<Result<Listing, OtherAppListing>, serde_json::Error> {
+    match appid {
+        Some(appid) if appid != APPID_TEAM_FORTRESS_2 => {
+            let listing = match serde_json::from_str::<GenericListing>(payload.get()) {
+                Ok(listing) => Some(Box::new(listing)),
+                Err(error) => {
+                    log::debug!("Error deserializing listing for appid {appid}: {error}");
+                    None
+                },
+            };
         
-        match message.event {
-            ListingUpdate |
-            ListingDelete => {
-                match serde_json::from_str::<Listing>(message.payload.get()) {
-                    Ok(listing) => {
-                        match message.event {
-                            EventType::ListingUpdate => Ok(Event {
-                                id: message.id,
-                                message: Message::ListingUpdate(listing)
-                            }),
-                            EventType::ListingDelete => Ok(Event {
-                                id: message.id,
-                                message: Message::ListingDelete(listing)
-                            }),
-                            _ => Err(EventError::Unreachable),
-                        }
-                    },
-                    Err(error) => {
-                        if let Ok(AppType { appid }) = serde_json::from_str::<AppType>(message.payload.get()) {
-                            if appid != APPID_TEAM_FORTRESS_2 {
-                                // move the payload into a box
-                                let payload = message.payload.to_owned();
+            Ok(Err((appid, listing, payload.to_owned())))
+        },
+        _ => serde_json::from_str::<Listing>(payload.get()).map(Ok),
+    }
+}
                                 
-                                return match message.event {
-                                    EventType::ListingUpdate => Ok(Event {
-                                        id: message.id,
-                                        message: Message::ListingUpdateOtherApp {
-                                            appid,
-                                            payload,
-                                        },
-                                    }),
-                                    EventType::ListingDelete => Ok(Event {
-                                        id: message.id,
-                                        message: Message::ListingDeleteOtherApp {
-                                            appid,
-                                            payload,
-                                        },
-                                    }),
-                                    // Shouldn't be reachable
-                                    _ => Err(EventError::Unreachable),
-                                };
-                            }
+/// Parses a batch of events from a text frame. Each event is parsed separately so an event which
+/// fails to parse does not affect the other events in the batch. The appid of each listing is read
+/// once, along with the fields the filter needs, and listings which don't match the filter are
+/// skipped before they are parsed. Listings which can't be summarized pass the filter so the
+/// parse error can be surfaced. Returns an error only if the frame is not a JSON array.
+pub fn parse_events(
+    bytes: &[u8],
+    filter: Option<&Filter>,
+) -> Result<Vec<(String, Message)>, serde_json::Error> {
+    let events = serde_json::from_slice::<Vec<&RawValue>>(bytes)?;
+    let messages = events
+        .into_iter()
+        .filter_map(|event| match serde_json::from_str::<EventMessage>(event.get()) {
+            Ok(message) => {
+                let is_listing = matches!(
+                    message.event.as_str(),
+                    EVENT_LISTING_UPDATE | EVENT_LISTING_DELETE,
+                );
+                        
+                let appid = match filter {
+                    Some(filter) if is_listing => {
+                        let summary = ListingSummary::from_payload(message.payload);
+                
+                        if summary.as_ref().is_some_and(|summary| !filter.matches(summary)) {
+                            return None;
                         }
                         
-                        return Err(EventError::Serde(error, message.payload));
+                        summary.map(|summary| summary.appid)
                     },
-                }
+                    None if is_listing => serde_json::from_str::<AppType>(message.payload.get())
+                        .ok()
+                        .map(|app_type| app_type.appid),
+                    _ => None,
+                };
+                
+                Some(message.into_message(appid))
             },
-            EventType::ClientLimitExceeded => {
-                // move the payload into a box
-                if let Ok(string_message) = serde_json::from_str::<StringMessage>(message.payload.get()) {
-                    return Ok(Event {
-                        id: message.id,
-                        message: Message::ClientLimitExceeded(string_message.message),
-                    });
-                }
+            Err(error) => {
+                log::debug!("Error deserializing event: {error}\n\n{event}");
                 
-                Ok(Event {
-                    id: message.id,
-                    message: Message::ClientLimitExceeded(message.payload.get().to_owned()),
-                })
+                Some((String::new(), Message::ParseError {
+                    event: None,
+                    error: error.to_string(),
+                    payload: event.to_owned(),
+                }))
             },
+        })
+        .collect();
+    
+    Ok(messages)
+}
+
+/// The reason reading events stopped.
+#[derive(Debug)]
+pub enum Disconnect {
+    /// The event stream was dropped.
+    ReceiverDropped,
+    /// The server closed the connection.
+    Closed(Option<CloseFrame>),
+    /// The connection errored.
+    Error(tungstenite::Error),
+    /// The stream ended without a close frame.
+    StreamEnded,
+    /// No events were received for the duration.
+    Stalled(Duration),
+}
+
+impl fmt::Display for Disconnect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Disconnect::ReceiverDropped => write!(f, "Event stream dropped"),
+            Disconnect::Closed(Some(frame)) => write!(f, "Connection closed: {frame}"),
+            Disconnect::Closed(None) => write!(f, "Connection closed"),
+            Disconnect::Error(error) => write!(f, "Connection dropped: {error}"),
+            Disconnect::StreamEnded => write!(f, "Stream ended"),
+            Disconnect::Stalled(duration) => write!(f, "No events received for {duration:?}"),
         }
     }
 }
 
+/// Reads events from the stream and sends them to the sender until the connection ends, the
+/// connection stalls with [`StallAction::Reconnect`], or the receiver is dropped. Text frames are
+/// recorded before they are parsed if the connection has a recorder.
 pub async fn read_events(
     mut stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
-    sender: mpsc::Sender<(String, Message)>,
-) {
-    while let Some(message) = stream.next().await {
+    sender: &EventSender,
+    connection: &Connection,
+) -> Disconnect {
+    let mut monitor = Monitor::new(connection.health, connection.stats.clone());
+    
+    loop {
+        let deadline = monitor.deadline();
+        let timer = pin!(async move {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        });
+        let message = match select(stream.next(), timer).await {
+            Either::Left((Some(message), _timer)) => message,
+            Either::Left((None, _timer)) => return Disconnect::StreamEnded,
+            Either::Right(_) => {
+                if let Some(disconnect) = on_timer(&mut stream, sender, &mut monitor).await {
+                    return disconnect;
+                }
+                
+                continue;
+            },
+        };
+        
         match message {
             Ok(WsMessage::Text(bytes)) => {
                 if bytes.is_empty() {
@@ -156,22 +240,26 @@ pub async fn read_events(
                     continue;
                 }
                 
-                match serde_json::from_slice::<Vec<EventMessage>>(bytes.as_ref()) {
+                let received_at = Utc::now();
+                
+                if let Some(Err(error)) = connection.recorder.as_ref().map(|recorder| recorder.record_at(received_at, bytes.as_str())) {
+                    log::debug!("Error recording frame: {error}");
+                }
+                
+                match parse_events(bytes.as_ref(), connection.filter.as_ref()) {
                     Ok(messages) => {
-                        for message in messages {
-                            // parse and send the message to the sender, capturing any errors in the message
-                            match on_event(message, &sender).await {
-                                Ok(_) => {},
-                                Err(EventError::Serde(error, payload)) => {
-                                    log::debug!("Error deserializing event payload: {error}\n\n{payload}");
-                                },
-                                // This means the channel is closed, so we can stop reading messages.
-                                Err(EventError::Send(_)) => break,
-                                Err(EventError::Unreachable) => continue,
+                        monitor.on_frame(messages.len());
+                        
+                        for event in Event::batch(messages, received_at) {
+                            // This means the channel is closed, so we can stop reading messages.
+                            if !sender.send(event).await {
+                                return Disconnect::ReceiverDropped;
                             }
                         }
                     },
                     Err(error) => {
+                        monitor.on_frame(0);
+                        
                         // If we encounter an error deserializing the event, log it.
                         if let Ok(message) = std::str::from_utf8(bytes.as_ref()) {
                             log::debug!("Error deserializing event: {error} {message}");
@@ -186,28 +274,116 @@ pub async fn read_events(
             // If we receive a close frame, we can stop reading messages.
             Ok(WsMessage::Close(frame)) => {
                 log::debug!("Connection closed: {:?}", frame);
-                break;
+                return Disconnect::Closed(frame);
             },
             Ok(WsMessage::Frame(frame)) => log::debug!("Frame received: {}", frame),
-            // Ping/pongs are handled automatically by the library, so we can ignore them.
-            Ok(WsMessage::Ping(_)) |
-            Ok(WsMessage::Pong(_)) => {},
+            // Pongs are used to measure the round-trip time.
+            Ok(WsMessage::Pong(payload)) => monitor.on_pong(&payload),
+            // Pings are handled automatically by the library, so we can ignore them.
+            Ok(WsMessage::Ping(_)) => {},
             Err(error) => {
                 // dropped?
                 log::debug!("Connection dropped: {}", error);
-                break;
+                return Disconnect::Error(error);
+            },
+        }
+    }
+}
+
+/// Takes the actions which are due for the health monitor. Returns the reason to disconnect if
+/// the connection should be dropped.
+async fn on_timer(
+    stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    sender: &EventSender,
+    monitor: &mut Monitor,
+) -> Option<Disconnect> {
+    for action in monitor.on_timer() {
+        match action {
+            HealthAction::Ping(payload) => {
+                if let Err(error) = stream.send(WsMessage::Ping(payload.into())).await {
+                    log::debug!("Connection dropped: {}", error);
+                    return Some(Disconnect::Error(error));
+                }
+            },
+            HealthAction::Stalled(duration) => {
+                if monitor.on_stall() == Some(StallAction::Reconnect) {
+                    log::debug!("Connection stalled: {:?}", duration);
+                    return Some(Disconnect::Stalled(duration));
+                }
+    
+                if !sender.send(Event::lifecycle(Message::Stalled(duration))).await {
+                    return Some(Disconnect::ReceiverDropped);
+                }
             },
         }
     }
+    
+    None
 }
 
-/// Handles an event.
-async fn on_event<'a>(
-    message: EventMessage<'a>,
-    sender: &mpsc::Sender<(String, Message)>,
-) -> Result<(), EventError<'a>> {
-    let Event { id, message } = Event::try_from(message)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_events_separately() {
+        let listing = include_str!("../response/listing/fixtures/websocket_listing.json");
+        let frame = format!(r#"[
+            {{"id": "1", "event": "listing-update", "payload": {listing}}},
+            {{"id": "2", "event": "listing-bump", "payload": {{"id": "440_1"}}}},
+            {{"id": "3", "event": "listing-delete", "payload": {{"appid": 440}}}},
+            {{"event": "listing-update"}}
+        ]"#);
+        let messages = parse_events(frame.as_bytes(), None).unwrap();
+        
+        assert!(matches!(&messages[0], (id, Message::ListingUpdate(_)) if id == "1"));
+        assert!(matches!(&messages[1], (id, Message::Unknown { event, .. }) if id == "2" && event == "listing-bump"));
+        assert!(matches!(
+            &messages[2],
+            (id, Message::ParseError { event: Some(event), payload, .. })
+                if id == "3" && event == "listing-delete" && payload.get() == r#"{"appid": 440}"#
+        ));
+        assert!(matches!(&messages[3], (_, Message::ParseError { event: None, .. })));
+    }
     
-    sender.send((id, message)).await?;
-    Ok(())
+    #[test]
+    fn skips_filtered_listings() {
+        let listing = include_str!("../response/listing/fixtures/websocket_listing.json");
+        let frame = format!(r#"[
+            {{"id": "1", "event": "listing-update", "payload": {listing}}},
+            {{"id": "2", "event": "client-limit-exceeded", "payload": {{"message": "Too many clients"}}}}
+        ]"#);
+        let filter = Filter::new().defindex(1);
+        let messages = parse_events(frame.as_bytes(), Some(&filter)).unwrap();
+        
+        assert!(matches!(messages.as_slice(), [(_, Message::ClientLimitExceeded(message))] if message == "Too many clients"));
+    }
+    
+    #[test]
+    fn parses_other_app_listings() {
+        let listing = include_str!("../response/listing/fixtures/websocket_listing_csgo.json");
+        let frame = format!(r#"[
+            {{"id": "1", "event": "listing-update", "payload": {listing}}},
+            {{"id": "2", "event": "listing-delete", "payload": {{"appid": 730}}}}
+        ]"#);
+        let messages = parse_events(frame.as_bytes(), None).unwrap();
+        
+        assert!(matches!(
+            &messages[0],
+            (_, Message::ListingUpdateOtherApp { appid: 730, listing: Some(listing), .. })
+                if listing.item.name == "Desert Eagle | Blaze (Factory New)"
+        ));
+        // Listings which can't be parsed still include their payload
+        assert!(matches!(
+            &messages[1],
+            (_, Message::ListingDeleteOtherApp { appid: 730, listing: None, payload })
+                if payload.get() == r#"{"appid": 730}"#
+        ));
+        
+        // The appid read by the filter is used to choose the model
+        let messages = parse_events(frame.as_bytes(), Some(&Filter::new().appid(730))).unwrap();
+        
+        assert!(matches!(&messages[0], (_, Message::ListingUpdateOtherApp { appid: 730, listing: Some(_), .. })));
+        assert!(matches!(&messages[1], (_, Message::ListingDeleteOtherApp { appid: 730, listing: None, .. })));
+    }
 }
@@ -0,0 +1,275 @@
+//! Health monitoring and stall detection.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The window over which events per minute are counted.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// What to do when no events arrive within the stall threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StallAction {
+    /// Send a [`Message::Stalled`](super::Message::Stalled) message. The message is sent once
+    /// per stall and the connection is kept open.
+    #[default]
+    Notify,
+    /// Drop the connection. When connected with
+    /// [`connect_with_reconnect`](super::connect_with_reconnect) this forces a reconnect,
+    /// otherwise the stream ends.
+    Reconnect,
+}
+
+/// Options for monitoring the health of the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthOptions {
+    /// How long to wait for an event before the connection is considered stalled.
+    pub stall_threshold: Duration,
+    /// What to do when the connection stalls.
+    pub on_stall: StallAction,
+    /// How often to ping the server to measure the round-trip time, or `None` to not send pings.
+    pub ping_interval: Option<Duration>,
+}
+
+impl Default for HealthOptions {
+    fn default() -> Self {
+        Self {
+            stall_threshold: Duration::from_secs(60),
+            on_stall: StallAction::default(),
+            ping_interval: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// A handle to the health statistics of a connection, from
+/// [`WebsocketBuilder::health_stats`](super::WebsocketBuilder::health_stats). Clones share the
+/// same statistics, which are kept across reconnects.
+#[derive(Debug, Clone, Default)]
+pub struct HealthStats {
+    inner: Arc<Mutex<Stats>>,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    last_event: Option<Instant>,
+    /// The number of events sent for each frame received within the rate window.
+    recent: VecDeque<(Instant, usize)>,
+    ping_rtt: Option<Duration>,
+}
+
+impl Stats {
+    /// Removes frames which are outside of the rate window.
+    fn prune(&mut self, now: Instant) {
+        while self.recent.front().is_some_and(|(at, _count)| now.duration_since(*at) > RATE_WINDOW) {
+            self.recent.pop_front();
+        }
+    }
+}
+
+impl HealthStats {
+    /// Creates new empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// The time since the last event was received from the server, including events removed by
+    /// a [`Filter`](super::Filter). `None` if no events have been received.
+    pub fn since_last_event(&self) -> Option<Duration> {
+        self.inner.lock().unwrap().last_event.map(|last_event| last_event.elapsed())
+    }
+    
+    /// The number of events sent to the stream in the last minute.
+    pub fn events_per_minute(&self) -> usize {
+        let mut stats = self.inner.lock().unwrap();
+        
+        stats.prune(Instant::now());
+        stats.recent
+            .iter()
+            .map(|(_at, count)| count)
+            .sum()
+    }
+    
+    /// The most recently measured ping round-trip time. `None` if no pong has been received.
+    pub fn ping_rtt(&self) -> Option<Duration> {
+        self.inner.lock().unwrap().ping_rtt
+    }
+    
+    /// Records a frame received from the server with the number of events sent from it.
+    pub(crate) fn record_frame(&self, count: usize) {
+        let now = Instant::now();
+        let mut stats = self.inner.lock().unwrap();
+        
+        stats.last_event = Some(now);
+        stats.prune(now);
+        
+        if count > 0 {
+            stats.recent.push_back((now, count));
+        }
+    }
+    
+    fn record_ping_rtt(&self, rtt: Duration) {
+        self.inner.lock().unwrap().ping_rtt = Some(rtt);
+    }
+}
+
+/// What the monitor needs done when its timer fires.
+#[derive(Debug, PartialEq)]
+pub(crate) enum HealthAction {
+    /// Send a ping with the payload.
+    Ping(Vec<u8>),
+    /// The connection stalled, no events were received for the duration.
+    Stalled(Duration),
+}
+
+/// Tracks the health of a single connection.
+#[derive(Debug)]
+pub(crate) struct Monitor {
+    options: Option<HealthOptions>,
+    stats: HealthStats,
+    last_event: Instant,
+    is_stalled: bool,
+    next_ping: Option<Instant>,
+    /// The payload and send time of the ping waiting for a pong.
+    pending_ping: Option<(u64, Instant)>,
+    ping_count: u64,
+}
+
+impl Monitor {
+    /// Creates a monitor for a connection which was just opened.
+    pub(crate) fn new(
+        options: Option<HealthOptions>,
+        stats: HealthStats,
+    ) -> Self {
+        let now = Instant::now();
+        
+        Self {
+            options,
+            stats,
+            last_event: now,
+            is_stalled: false,
+            next_ping: options
+                .and_then(|options| options.ping_interval)
+                .map(|interval| now + interval),
+            pending_ping: None,
+            ping_count: 0,
+        }
+    }
+    
+    /// The action to take when the connection stalls, if stall detection is enabled.
+    pub(crate) fn on_stall(&self) -> Option<StallAction> {
+        self.options.map(|options| options.on_stall)
+    }
+    
+    /// When the timer should next fire, or `None` if there is nothing to wait for.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        let stall = self.options
+            .filter(|_| !self.is_stalled)
+            .map(|options| self.last_event + options.stall_threshold);
+        
+        match (stall, self.next_ping) {
+            (Some(stall), Some(ping)) => Some(stall.min(ping)),
+            (stall, ping) => stall.or(ping),
+        }
+    }
+    
+    /// Records a frame with the number of events sent from it.
+    pub(crate) fn on_frame(&mut self, count: usize) {
+        self.last_event = Instant::now();
+        self.is_stalled = false;
+        self.stats.record_frame(count);
+    }
+    
+    /// Records a pong from the server.
+    pub(crate) fn on_pong(&mut self, payload: &[u8]) {
+        let Some((ping, sent_at)) = self.pending_ping else {
+            return;
+        };
+        
+        if payload == ping.to_be_bytes().as_slice() {
+            self.stats.record_ping_rtt(sent_at.elapsed());
+            self.pending_ping = None;
+        }
+    }
+    
+    /// Gets the actions which are due.
+    pub(crate) fn on_timer(&mut self) -> Vec<HealthAction> {
+        let now = Instant::now();
+        let mut actions = Vec::new();
+        let Some(options) = self.options else {
+            return actions;
+        };
+        
+        let is_ping_due = self.next_ping.is_some_and(|next_ping| next_ping <= now);
+        
+        if let Some(interval) = options.ping_interval.filter(|_| is_ping_due) {
+            self.ping_count += 1;
+            self.pending_ping = Some((self.ping_count, now));
+            self.next_ping = Some(now + interval);
+            actions.push(HealthAction::Ping(self.ping_count.to_be_bytes().to_vec()));
+        }
+        
+        let since_last_event = now.duration_since(self.last_event);
+        
+        if !self.is_stalled && since_last_event >= options.stall_threshold {
+            self.is_stalled = true;
+            actions.push(HealthAction::Stalled(since_last_event));
+        }
+        
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn detects_stalls_once() {
+        let options = HealthOptions {
+            stall_threshold: Duration::from_millis(20),
+            on_stall: StallAction::Notify,
+            ping_interval: None,
+        };
+        let stats = HealthStats::new();
+        let mut monitor = Monitor::new(Some(options), stats.clone());
+        
+        monitor.on_frame(3);
+        assert!(monitor.on_timer().is_empty());
+        assert_eq!(stats.events_per_minute(), 3);
+        
+        tokio::time::sleep_until(monitor.deadline().unwrap()).await;
+        assert!(matches!(monitor.on_timer().as_slice(), [HealthAction::Stalled(_)]));
+        // The stall is only reported once until another event arrives
+        assert!(monitor.deadline().is_none());
+        assert!(monitor.on_timer().is_empty());
+        
+        monitor.on_frame(0);
+        assert!(monitor.deadline().is_some());
+        assert_eq!(stats.events_per_minute(), 3);
+        assert!(stats.since_last_event().unwrap() < Duration::from_millis(20));
+    }
+    
+    #[tokio::test]
+    async fn measures_ping_rtt() {
+        let options = HealthOptions {
+            stall_threshold: Duration::from_secs(60),
+            on_stall: StallAction::Notify,
+            ping_interval: Some(Duration::from_millis(1)),
+        };
+        let stats = HealthStats::new();
+        let mut monitor = Monitor::new(Some(options), stats.clone());
+        
+        tokio::time::sleep_until(monitor.deadline().unwrap()).await;
+        
+        let actions = monitor.on_timer();
+        let [HealthAction::Ping(payload)] = actions.as_slice() else {
+            panic!("Expected a ping: {actions:?}");
+        };
+        
+        monitor.on_pong(b"other");
+        assert!(stats.ping_rtt().is_none());
+        monitor.on_pong(payload);
+        assert!(stats.ping_rtt().is_some());
+    }
+}
@@ -1,7 +1,8 @@
 //! A parsed message from the websocket.
 
-use crate::response::listing::Listing;
+use crate::response::listing::{GenericListing, Listing};
 use std::fmt;
+use std::time::Duration;
 use serde_json::value::RawValue;
 
 /// A message from the websocket.
@@ -11,26 +12,75 @@ pub enum Message {
     ListingUpdate(Listing),
     /// A listing was deleted.
     ListingDelete(Listing),
-    /// A listing from another app other than Team Fortress 2 was updated. Because of the way
-    /// listings are deserialized, other apps are not supported by this crate. You can still
-    /// deserialze them from the payload if needed.
+    /// A listing from another app other than Team Fortress 2 was updated. The listing only
+    /// includes fields common to all apps. App-specific fields can be deserialized from the
+    /// payload if needed.
     ListingUpdateOtherApp {
         /// The appid of the listing.
         appid: u32,
+        /// The listing, or `None` if the payload couldn't be parsed as a [`GenericListing`].
+        listing: Option<Box<GenericListing>>,
         /// The payload of the event.
         payload: Box<RawValue>,
     },
-    /// A listing from another app other than Team Fortress 2 was deleted. Because of the way
-    /// listings are deserialized, other apps are not supported by this crate. You can still
-    /// deserialze them from the payload if needed.
+    /// A listing from another app other than Team Fortress 2 was deleted. The listing only
+    /// includes fields common to all apps. App-specific fields can be deserialized from the
+    /// payload if needed.
     ListingDeleteOtherApp {
         /// The appid of the listing.
         appid: u32,
+        /// The listing, or `None` if the payload couldn't be parsed as a [`GenericListing`].
+        listing: Option<Box<GenericListing>>,
         /// The payload of the event.
         payload: Box<RawValue>,
     },
     /// The client was exceeded. The contained string contains more details.
     ClientLimitExceeded(String),
+    /// An event with an unrecognised type was received.
+    Unknown {
+        /// The type of event.
+        event: String,
+        /// The payload of the event.
+        payload: Box<RawValue>,
+    },
+    /// An event could not be parsed.
+    ParseError {
+        /// The type of event, or `None` if the event itself could not be read.
+        event: Option<String>,
+        /// The parse error.
+        error: String,
+        /// The payload of the event, or the whole event if the event itself could not be read.
+        payload: Box<RawValue>,
+    },
+    /// The client connected to the websocket. Only sent by
+    /// [`connect_with_reconnect`](super::connect_with_reconnect). Lifecycle messages are sent
+    /// with an empty event ID.
+    Connected,
+    /// The connection was lost or a connection attempt failed. The contained string describes
+    /// the reason.
+    Disconnected(String),
+    /// No events were received for the duration. Only sent when
+    /// [`HealthOptions`](super::HealthOptions) are set with [`StallAction::Notify`](super::StallAction::Notify).
+    /// Sent once per stall with an empty event ID.
+    Stalled(Duration),
+    /// The client is about to reconnect. The contained number is the reconnect attempt since the
+    /// last successful connection, starting at 1.
+    Reconnecting(u32),
+}
+
+impl Message {
+    /// Whether the message is a lifecycle message sent by the client rather than an event from
+    /// backpack.tf. Lifecycle messages are never dropped by the
+    /// [`Backpressure`](super::Backpressure) policy.
+    pub fn is_lifecycle(&self) -> bool {
+        matches!(
+            self,
+            Message::Connected |
+            Message::Disconnected(_) |
+            Message::Stalled(_) |
+            Message::Reconnecting(_)
+        )
+    }
 }
 
 impl fmt::Display for Message {
@@ -38,13 +88,29 @@ impl fmt::Display for Message {
         match self {
             Message::ListingUpdate(listing) => write!(f, "ListingUpdate: {}", listing),
             Message::ListingDelete(listing) => write!(f, "ListingDelete: {}", listing),
-            Message::ListingUpdateOtherApp { appid, payload } => {
+            Message::ListingUpdateOtherApp { appid, listing: Some(listing), .. } => {
+                write!(f, "ListingUpdateOtherApp: appid={}, item={}", appid, listing.item.name)
+            }
+            Message::ListingUpdateOtherApp { appid, listing: None, payload } => {
                 write!(f, "ListingUpdateOtherApp: appid={}, payload={}", appid, payload)
             }
-            Message::ListingDeleteOtherApp { appid, payload } => {
+            Message::ListingDeleteOtherApp { appid, listing: Some(listing), .. } => {
+                write!(f, "ListingDeleteOtherApp: appid={}, item={}", appid, listing.item.name)
+            }
+            Message::ListingDeleteOtherApp { appid, listing: None, payload } => {
                 write!(f, "ListingDeleteOtherApp: appid={}, payload={}", appid, payload)
             }
             Message::ClientLimitExceeded(message) => write!(f, "ClientLimitExceeded: {}", message),
+            Message::Unknown { event, payload } => {
+                write!(f, "Unknown: event={}, payload={}", event, payload)
+            }
+            Message::ParseError { event, error, payload } => {
+                write!(f, "ParseError: event={:?}, error={}, payload={}", event, error, payload)
+            }
+            Message::Connected => write!(f, "Connected"),
+            Message::Disconnected(reason) => write!(f, "Disconnected: {}", reason),
+            Message::Reconnecting(attempt) => write!(f, "Reconnecting: attempt={}", attempt),
+            Message::Stalled(duration) => write!(f, "Stalled: {:?}", duration),
         }
     }
 }
\ No newline at end of file
@@ -3,32 +3,43 @@
 
 mod message;
 mod handlers;
+mod reconnect;
+mod filter;
+mod builder;
+mod backpressure;
+mod record;
+mod event;
+mod health;
 
 pub use message::Message;
+pub use filter::{Filter, ListingSummary};
+pub use reconnect::{ReconnectOptions, connect_with_reconnect};
+pub use builder::WebsocketBuilder;
+pub use backpressure::{Backpressure, DroppedCounter};
+pub use record::{Recorder, Replay, ReplayPace};
+pub use event::{Event, EventStream};
+pub use health::{HealthOptions, HealthStats, StallAction};
+pub use tokio_tungstenite::Connector;
 pub use tungstenite::Error;
 
-use handlers::read_events;
-
-use tokio::sync::mpsc;
-use tokio_tungstenite::{tungstenite, connect_async};
-use tungstenite::client::IntoClientRequest;
-
-/// The websocket message receiver.
-pub type Receiver = mpsc::Receiver<(String, Message)>;
+use tokio_tungstenite::tungstenite;
 
 const CONNECT_ADDR: &str = "wss://ws.backpack.tf/events";
 
-/// Connects to the websocket.
+/// Connects to the websocket. Use [`WebsocketBuilder`] to configure the connection.
+/// 
+/// Dropping the stream closes the connection.
+pub async fn connect() -> Result<EventStream, tungstenite::Error> {
+    WebsocketBuilder::new().connect().await
+}
+
+/// Connects to the websocket, only sending listings which match the filter. Listings which don't
+/// match are skipped before they are fully parsed.
 /// 
-/// Dropping the receiver closes the connection.
-pub async fn connect() -> Result<mpsc::Receiver<(String, Message)>, tungstenite::Error> {
-    // The address to connect to.
-    let request = CONNECT_ADDR
-        .into_client_request()?;
-    let (stream, _) = connect_async(request.clone()).await?;
-    let (sender, read) = mpsc::channel::<(String, Message)>(100);
-    
-    tokio::spawn(read_events(stream, sender));
-    
-    Ok(read)
+/// Dropping the stream closes the connection.
+pub async fn connect_filtered(filter: Filter) -> Result<EventStream, tungstenite::Error> {
+    WebsocketBuilder::new()
+        .filter(filter)
+        .connect()
+        .await
 }
@@ -0,0 +1,295 @@
+//! Reconnecting websocket client.
+
+use super::{Event, EventStream, Message, WebsocketBuilder};
+use super::backpressure::EventSender;
+use super::builder::Connection;
+use super::handlers::{read_events, Disconnect};
+use crate::CancellationToken;
+use std::future::Future;
+use std::pin::pin;
+use std::time::{Duration, Instant};
+use futures_util::future::{select, Either};
+use rand::Rng;
+
+/// Options for reconnecting to the websocket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectOptions {
+    /// The wait before the first reconnect attempt. This doubles for each following attempt
+    /// until a connection is stable.
+    pub initial_backoff: Duration,
+    /// The maximum wait between reconnect attempts.
+    pub max_backoff: Duration,
+    /// The fraction of each wait which is randomized, from 0.0 to 1.0. A jitter of 0.5 waits
+    /// between 50% and 100% of the backoff, which keeps many clients from reconnecting at once.
+    pub jitter: f64,
+    /// How long a connection must stay open before it is stable. The backoff is reset once a
+    /// connection is stable or delivers an event, so a server which accepts connections and
+    /// drops them straight away is still backed off from.
+    pub stable_after: Duration,
+}
+
+impl Default for ReconnectOptions {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            jitter: 0.5,
+            stable_after: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectOptions {
+    /// Gets the wait before the given reconnect attempt, starting at 1, without jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let multiplier = 2u32.saturating_pow(attempt.saturating_sub(1));
+        
+        self.initial_backoff
+            .saturating_mul(multiplier)
+            .min(self.max_backoff)
+    }
+    
+    /// Gets the wait before the given reconnect attempt, starting at 1, with jitter applied.
+    pub fn jittered_backoff(&self, attempt: u32) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let scale = 1.0 - jitter * rand::rng().random::<f64>();
+        
+        self.backoff(attempt).mul_f64(scale)
+    }
+}
+
+/// Connects to the websocket, reconnecting with a jittered exponential backoff whenever the
+/// connection is lost. The same stream is used across reconnects and lifecycle messages
+/// ([`Message::Connected`], [`Message::Disconnected`] and [`Message::Reconnecting`]) are sent
+/// as the connection changes. Lifecycle messages are sent with an empty event ID and are never
+/// dropped by the [`Backpressure`](super::Backpressure) policy.
+///
+/// The client stops when the stream is dropped or the token is cancelled. Must be called from
+/// within a tokio runtime. Use [`WebsocketBuilder::connect_with_reconnect`] to configure the
+/// connection.
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::CancellationToken;
+/// use backpacktf_api::websocket::{Message, ReconnectOptions, connect_with_reconnect};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let shutdown = CancellationToken::new();
+///     let mut websocket = connect_with_reconnect(ReconnectOptions::default(), shutdown.clone());
+///
+///     while let Some(event) = websocket.recv().await {
+///         match event.message {
+///             Message::Disconnected(reason) => println!("Disconnected: {reason}"),
+///             Message::ClientLimitExceeded(_) => shutdown.cancel(),
+///             message => println!("{message}"),
+///         }
+///     }
+/// }
+/// ```
+pub fn connect_with_reconnect(
+    options: ReconnectOptions,
+    cancel: CancellationToken,
+) -> EventStream {
+    WebsocketBuilder::new()
+        .connect_with_reconnect(options, cancel)
+        .expect("Default connection request is valid")
+}
+
+/// Connects and reads events in a loop until stopped.
+pub(crate) async fn run(
+    connection: Connection,
+    options: ReconnectOptions,
+    sender: EventSender,
+    cancel: CancellationToken,
+) {
+    let mut attempt = 0;
+    
+    loop {
+        let connected = until_stopped(connection.open(), &sender, &cancel).await;
+        let reason = match connected {
+            None => return,
+            Some(Ok(stream)) => {
+                let connected_at = Instant::now();
+                
+                if !send(&sender, Message::Connected).await {
+                    return;
+                }
+                
+                let disconnect = match until_stopped(read_events(stream, &sender, &connection), &sender, &cancel).await {
+                    None | Some(Disconnect::ReceiverDropped) => return,
+                    Some(disconnect) => disconnect,
+                };
+                let uptime = connected_at.elapsed();
+                let delivered = connection.stats
+                    .since_last_event()
+                    .is_some_and(|since_last_event| since_last_event < uptime);
+                
+                if uptime >= options.stable_after || delivered {
+                    attempt = 0;
+                }
+                
+                disconnect.to_string()
+            },
+            Some(Err(error)) => format!("Connection failed: {error}"),
+        };
+        
+        attempt += 1;
+        
+        if !send(&sender, Message::Disconnected(reason)).await {
+            return;
+        }
+        
+        let backoff = options.jittered_backoff(attempt);
+        
+        if until_stopped(tokio::time::sleep(backoff), &sender, &cancel).await.is_none() {
+            return;
+        }
+        
+        if !send(&sender, Message::Reconnecting(attempt)).await {
+            return;
+        }
+    }
+}
+
+/// Sends a lifecycle message. Returns `false` if the stream was dropped.
+async fn send(
+    sender: &EventSender,
+    message: Message,
+) -> bool {
+    sender.send_lifecycle(Event::lifecycle(message)).await
+}
+
+/// Runs the future until it completes, the stream is dropped, or the token is cancelled.
+/// Returns `None` if stopped before the future completed.
+async fn until_stopped<F>(
+    future: F,
+    sender: &EventSender,
+    cancel: &CancellationToken,
+) -> Option<F::Output>
+where
+    F: Future,
+{
+    let closed = pin!(sender.closed());
+    let cancelled = pin!(cancel.cancelled());
+    
+    match select(pin!(future), select(closed, cancelled)).await {
+        Either::Left((output, _stopped)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+    use futures_util::SinkExt;
+    
+    #[test]
+    fn jittered_backoff_stays_within_bounds() {
+        let options = ReconnectOptions {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            jitter: 0.5,
+            ..Default::default()
+        };
+        
+        assert_eq!(options.backoff(1), Duration::from_secs(1));
+        assert_eq!(options.backoff(3), Duration::from_secs(4));
+        assert_eq!(options.backoff(10), Duration::from_secs(30));
+        
+        for attempt in 1..10 {
+            let backoff = options.jittered_backoff(attempt);
+            
+            assert!(backoff <= options.backoff(attempt));
+            assert!(backoff >= options.backoff(attempt) / 2);
+        }
+    }
+    
+    #[tokio::test]
+    async fn reconnects_until_cancelled() {
+        let options = ReconnectOptions {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let cancel = CancellationToken::new();
+        // Nothing listens on port 1 so each connection attempt fails
+        let mut stream = WebsocketBuilder::new()
+            .url("ws://127.0.0.1:1")
+            .connect_with_reconnect(options, cancel.clone())
+            .unwrap();
+        
+        assert!(matches!(stream.recv().await, Some(Event { message: Message::Disconnected(_), .. })));
+        assert!(matches!(stream.recv().await, Some(Event { message: Message::Reconnecting(1), .. })));
+        assert!(matches!(stream.recv().await, Some(Event { message: Message::Disconnected(_), .. })));
+        assert!(matches!(stream.recv().await, Some(Event { message: Message::Reconnecting(2), .. })));
+        
+        cancel.cancel();
+        
+        // The stream ends once the client stops
+        while let Some(event) = tokio::time::timeout(Duration::from_secs(5), stream.recv()).await.unwrap() {
+            assert!(matches!(event.message, Message::Disconnected(_) | Message::Reconnecting(_)));
+        }
+    }
+    
+    /// Starts a server which accepts every connection, sends the frame if there is one, then
+    /// closes the connection.
+    async fn start_closing_server(frame: Option<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let mut websocket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                
+                if let Some(frame) = frame {
+                    websocket.send(WsMessage::text(frame)).await.unwrap();
+                }
+                
+                let _ = websocket.close(None).await;
+            }
+        });
+        
+        format!("ws://{addr}")
+    }
+    
+    /// Gets the attempts of the first reconnects.
+    async fn reconnect_attempts(url: String) -> Vec<u32> {
+        let options = ReconnectOptions {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let cancel = CancellationToken::new();
+        let mut stream = WebsocketBuilder::new()
+            .url(url)
+            .connect_with_reconnect(options, cancel.clone())
+            .unwrap();
+        let mut attempts = Vec::new();
+        
+        while attempts.len() < 3 {
+            if let Some(Event { message: Message::Reconnecting(attempt), .. }) = stream.recv().await {
+                attempts.push(attempt);
+            }
+        }
+        
+        cancel.cancel();
+        attempts
+    }
+    
+    #[tokio::test]
+    async fn backs_off_from_connections_which_drop_straight_away() {
+        let url = start_closing_server(None).await;
+        
+        assert_eq!(reconnect_attempts(url).await, vec![1, 2, 3]);
+    }
+    
+    #[tokio::test]
+    async fn resets_backoff_once_an_event_is_delivered() {
+        let url = start_closing_server(Some(r#"[{"id": "1", "event": "client-limit-exceeded", "payload": {"message": "hi"}}]"#)).await;
+        
+        assert_eq!(reconnect_attempts(url).await, vec![1, 1, 1]);
+    }
+}
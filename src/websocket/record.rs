@@ -0,0 +1,386 @@
+//! Recording websocket frames to disk and replaying them.
+
+use super::{Event, EventStream, Filter};
+use super::handlers::parse_events;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::mpsc as std_mpsc;
+use std::thread::{self, JoinHandle};
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use futures_util::future::{select, Either};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// The default capacity of the replay channel.
+const DEFAULT_CHANNEL_CAPACITY: usize = 100;
+
+/// A line in a recording.
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedFrame {
+    /// When the frame was received.
+    received_at: DateTime<Utc>,
+    /// The raw text of the frame.
+    frame: String,
+}
+
+/// A command sent to the writer thread.
+enum Command {
+    /// Writes a frame.
+    Write(RecordedFrame),
+    /// Replies once every frame sent before it has been written.
+    Flush(std_mpsc::Sender<io::Result<()>>),
+}
+
+/// Records raw websocket frames to a gzip compressed JSONL file. Use
+/// [`WebsocketBuilder::record`](super::WebsocketBuilder::record) to record a connection and
+/// [`Replay`] to replay the recording.
+///
+/// Each line is a JSON object with the time the frame was received as `received_at` and the raw
+/// text of the frame as `frame`, before any filtering or parsing. Frames are written by a
+/// dedicated thread so recording never blocks the connection. The file is flushed after each
+/// frame so it can be replayed up to the last frame even if the program exits without dropping
+/// the recorder. Clones write to the same file and the file is finished once every clone is
+/// dropped.
+#[derive(Clone)]
+pub struct Recorder {
+    inner: Arc<RecorderInner>,
+}
+
+struct RecorderInner {
+    sender: Option<std_mpsc::Sender<Command>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Creates a recording at the path, replacing the file if it exists.
+    pub fn create<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::create(path)?;
+        let writer = GzEncoder::new(BufWriter::new(file), Compression::default());
+        let (sender, receiver) = std_mpsc::channel();
+        let writer = thread::Builder::new()
+            .name("backpacktf-recorder".into())
+            .spawn(move || write_frames(writer, receiver))?;
+        
+        Ok(Self {
+            inner: Arc::new(RecorderInner {
+                sender: Some(sender),
+                writer: Some(writer),
+            }),
+        })
+    }
+    
+    /// Records a frame received now.
+    pub fn record(&self, frame: &str) -> io::Result<()> {
+        self.record_at(Utc::now(), frame)
+    }
+    
+    /// Waits until every frame recorded so far has been written to the file. This blocks the
+    /// current thread.
+    pub fn flush(&self) -> io::Result<()> {
+        let (sender, receiver) = std_mpsc::channel();
+        
+        self.send(Command::Flush(sender))?;
+        receiver.recv().unwrap_or_else(|_| Err(stopped_error()))
+    }
+    
+    /// Records a frame received at the given time. The frame is queued for the writer thread,
+    /// so an error is only returned if the writer has stopped after failing to write an earlier
+    /// frame.
+    pub(crate) fn record_at(
+        &self,
+        received_at: DateTime<Utc>,
+        frame: &str,
+    ) -> io::Result<()> {
+        self.send(Command::Write(RecordedFrame {
+            received_at,
+            frame: frame.to_owned(),
+        }))
+    }
+    
+    fn send(&self, command: Command) -> io::Result<()> {
+        self.inner.sender
+            .as_ref()
+            .ok_or_else(stopped_error)?
+            .send(command)
+            .map_err(|_| stopped_error())
+    }
+}
+
+impl Drop for RecorderInner {
+    fn drop(&mut self) {
+        // Closing the channel stops the writer once the queued frames are written
+        drop(self.sender.take());
+        
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder").finish_non_exhaustive()
+    }
+}
+
+/// The error returned when the writer thread has stopped.
+fn stopped_error() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "Recorder stopped writing")
+}
+
+/// Writes frames until every recorder is dropped or a write fails.
+fn write_frames(
+    mut writer: GzEncoder<BufWriter<File>>,
+    receiver: std_mpsc::Receiver<Command>,
+) {
+    let write_frame = |writer: &mut GzEncoder<BufWriter<File>>, frame: &RecordedFrame| -> io::Result<()> {
+        let mut line = serde_json::to_vec(frame)?;
+        
+        line.push(b'\n');
+        writer.write_all(&line)?;
+        writer.flush()
+    };
+    
+    for command in receiver {
+        match command {
+            Command::Write(frame) => if let Err(error) = write_frame(&mut writer, &frame) {
+                log::debug!("Error recording frame: {error}");
+                return;
+            },
+            Command::Flush(reply) => {
+                let _ = reply.send(Ok(()));
+            },
+        }
+    }
+    
+    if let Err(error) = writer.finish() {
+        log::debug!("Error finishing recording: {error}");
+    }
+}
+
+/// How fast a recording is replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayPace {
+    /// Frames are sent with the same delays between them as when they were recorded.
+    #[default]
+    Original,
+    /// Frames are sent as fast as the stream is read.
+    Unlimited,
+}
+
+/// Replays a recording made by a [`Recorder`], sending the same messages a connection would.
+///
+/// # Examples
+/// ```no_run
+/// use backpacktf_api::websocket::{Replay, ReplayPace};
+///
+/// #[tokio::main]
+/// async fn main() -> std::io::Result<()> {
+///     let mut websocket = Replay::new("events.jsonl.gz")
+///         .pace(ReplayPace::Unlimited)
+///         .start()?;
+///
+///     while let Some(event) = websocket.recv().await {
+///         println!("{}", event.message);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Replay {
+    path: PathBuf,
+    pace: ReplayPace,
+    channel_capacity: usize,
+    filter: Option<Filter>,
+}
+
+impl Replay {
+    /// Creates a new replay of the recording at the path.
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            path: path.into(),
+            pace: ReplayPace::default(),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            filter: None,
+        }
+    }
+    
+    /// Sets how fast the recording is replayed. Defaults to [`ReplayPace::Original`].
+    pub fn pace(mut self, pace: ReplayPace) -> Self {
+        self.pace = pace;
+        self
+    }
+    
+    /// Sets the capacity of the message channel. The capacity is at least 1. Defaults to 100.
+    pub fn channel_capacity(mut self, channel_capacity: usize) -> Self {
+        self.channel_capacity = channel_capacity.max(1);
+        self
+    }
+    
+    /// Only sends listings which match the filter. See [`Filter`].
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+    
+    /// Opens the recording and starts sending events. The stream ends at the end of the
+    /// recording. Lines which can't be read are skipped and reading stops at the first I/O
+    /// error, such as a recording cut off while it was being written. Must be called from within
+    /// a tokio runtime.
+    ///
+    /// Dropping the stream stops the replay.
+    pub fn start(self) -> io::Result<EventStream> {
+        let file = File::open(&self.path)?;
+        let (frame_sender, frame_receiver) = mpsc::channel(self.channel_capacity);
+        let (sender, receiver) = mpsc::channel(self.channel_capacity);
+        
+        tokio::task::spawn_blocking(move || read_frames(file, frame_sender));
+        tokio::spawn(send_frames(frame_receiver, sender, self.pace, self.filter));
+        
+        Ok(EventStream::new(receiver))
+    }
+}
+
+/// Reads frames from the file until the end of the file or the stream is dropped.
+fn read_frames(
+    file: File,
+    sender: mpsc::Sender<RecordedFrame>,
+) {
+    for line in BufReader::new(GzDecoder::new(file)).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                log::debug!("Error reading recording: {error}");
+                return;
+            },
+        };
+        
+        if line.is_empty() {
+            continue;
+        }
+        
+        match serde_json::from_str::<RecordedFrame>(&line) {
+            Ok(frame) => if sender.blocking_send(frame).is_err() {
+                return;
+            },
+            Err(error) => log::debug!("Error deserializing recorded frame: {error}\n\n{line}"),
+        }
+    }
+}
+
+/// Parses frames and sends their events at the pace.
+async fn send_frames(
+    mut frames: mpsc::Receiver<RecordedFrame>,
+    sender: mpsc::Sender<Event>,
+    pace: ReplayPace,
+    filter: Option<Filter>,
+) {
+    // The time of the first frame and when it was replayed
+    let mut start: Option<(DateTime<Utc>, Instant)> = None;
+    
+    while let Some(RecordedFrame { received_at, frame }) = frames.recv().await {
+        if pace == ReplayPace::Original {
+            let (first_received_at, started) = *start.get_or_insert((received_at, Instant::now()));
+            let offset = (received_at - first_received_at).to_std().unwrap_or_default();
+            let sleep = pin!(tokio::time::sleep_until(started + offset));
+            
+            if let Either::Right(_) = select(sleep, pin!(sender.closed())).await {
+                return;
+            }
+        }
+        
+        let messages = match parse_events(frame.as_bytes(), filter.as_ref()) {
+            Ok(messages) => messages,
+            Err(error) => {
+                log::debug!("Error deserializing event: {error} {frame}");
+                continue;
+            },
+        };
+        
+        for event in Event::batch(messages, received_at) {
+            if sender.send(event).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::Message;
+    use std::time::Duration;
+    
+    fn get_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("backpacktf-api-{name}-{}.jsonl.gz", std::process::id()))
+    }
+    
+    #[tokio::test]
+    async fn replays_recording() {
+        let path = get_path("replay");
+        let listing = include_str!("../response/listing/fixtures/websocket_listing.json");
+        let recorder = Recorder::create(&path).unwrap();
+        let received_at = Utc::now();
+        
+        recorder.record_at(received_at, &format!(r#"[{{"id": "1", "event": "listing-update", "payload": {listing}}}]"#)).unwrap();
+        recorder.record_at(received_at + Duration::from_millis(200), "not json").unwrap();
+        recorder.record_at(received_at + Duration::from_millis(200), r#"[{"id": "2", "event": "client-limit-exceeded", "payload": {"message": "hi"}}]"#).unwrap();
+        drop(recorder);
+        
+        let started = Instant::now();
+        let mut stream = Replay::new(&path).start().unwrap();
+        
+        assert!(matches!(stream.recv().await, Some(Event { id, message: Message::ListingUpdate(_), .. }) if id == "1"));
+        assert!(matches!(stream.recv().await, Some(Event { id, message: Message::ClientLimitExceeded(_), .. }) if id == "2"));
+        assert!(stream.recv().await.is_none());
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        
+        let mut stream = Replay::new(&path)
+            .pace(ReplayPace::Unlimited)
+            .filter(Filter::new().defindex(1))
+            .start()
+            .unwrap();
+        
+        assert!(matches!(stream.recv().await, Some(Event { id, message: Message::ClientLimitExceeded(_), .. }) if id == "2"));
+        assert!(stream.recv().await.is_none());
+        
+        std::fs::remove_file(path).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn replays_unfinished_recording() {
+        let path = get_path("unfinished");
+        let recorder = Recorder::create(&path).unwrap();
+        
+        recorder.record(r#"[{"id": "1", "event": "client-limit-exceeded", "payload": {"message": "hi"}}]"#).unwrap();
+        recorder.flush().unwrap();
+        // Simulate the program exiting without finishing the file
+        std::mem::forget(recorder);
+        
+        // A capacity of 0 is clamped to 1
+        let mut stream = Replay::new(&path)
+            .channel_capacity(0)
+            .start()
+            .unwrap();
+        
+        assert!(matches!(stream.recv().await, Some(Event { id, message: Message::ClientLimitExceeded(_), .. }) if id == "1"));
+        assert!(stream.recv().await.is_none());
+        
+        std::fs::remove_file(path).unwrap();
+    }
+}